@@ -0,0 +1,569 @@
+//! Aggregations over log entries: per-field facets and group-by counts, time
+//! histograms and numeric field statistics.
+//!
+//! Facets track at most `MAX_TRACKED_VALUES` distinct values per field, so a
+//! high-cardinality field such as a request ID cannot grow memory with the buffer size.
+//! Up to that many values the counts are exact; beyond it the distinct count is a
+//! HyperLogLog++ estimate and the top values come from a space-saving summary, and the
+//! facet is flagged as approximate.
+
+use crate::{log_entry::DynamicFieldValue, LogEntry, LogLevel};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use hyperloglogplus::{HyperLogLog, HyperLogLogPlus};
+use serde::Serialize;
+use std::collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet, HashMap};
+use std::hash::{BuildHasherDefault, Hash};
+
+/// Distinct values (or value combinations) counted individually per facet or group-by.
+const MAX_TRACKED_VALUES: usize = 10_000;
+
+/// Precision of the distinct-count sketch: 2^14 registers, about 0.8% standard error.
+const DISTINCT_PRECISION: u8 = 14;
+
+/// Number of buckets targeted when the histogram interval is chosen automatically.
+const AUTO_HISTOGRAM_BUCKETS: i64 = 60;
+
+/// Upper bound on buckets per histogram, to keep explicit tiny intervals in check.
+const MAX_HISTOGRAM_BUCKETS: i64 = 2_000;
+
+/// Interval steps (in seconds) the automatic histogram interval is rounded up to.
+const NICE_INTERVALS: [i64; 19] = [
+    1, 2, 5, 10, 15, 30, 60, 120, 300, 600, 900, 1_800, 3_600, 7_200, 10_800, 21_600, 43_200,
+    86_400, 604_800,
+];
+
+const LEVELS: [LogLevel; 6] = [
+    LogLevel::Trace,
+    LogLevel::Debug,
+    LogLevel::Info,
+    LogLevel::Warn,
+    LogLevel::Error,
+    LogLevel::Fatal,
+];
+
+#[derive(Debug, Serialize)]
+pub struct AggregateResult {
+    pub total: usize,
+    pub facets: Vec<Facet>,
+    /// Top combinations when grouping by more than one field.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<GroupCount>,
+    /// More than `MAX_TRACKED_VALUES` combinations occurred, so `groups` counts are
+    /// estimates.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub groups_approximate: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Facet {
+    pub field: String,
+    /// Number of entries that contain the field.
+    pub count: usize,
+    pub missing: usize,
+    /// Distinct values seen; an estimate when `approximate` is set
+    pub distinct_count: usize,
+    /// More than `MAX_TRACKED_VALUES` distinct values occurred. `top` counts may then
+    /// overcount by values evicted from the summary, though no value frequent enough to
+    /// rank is missed.
+    pub approximate: bool,
+    /// Most frequent values
+    pub top: Vec<ValueCount>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupCount {
+    pub values: Vec<Option<String>>,
+    pub count: usize,
+}
+
+/// Builds a facet for every field and, for multiple fields, the top value combinations.
+pub fn aggregate<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    group_by: &[String],
+    top: usize,
+) -> AggregateResult {
+    let mut total = 0;
+    let mut value_counts: Vec<ValueCounter<String>> = group_by.iter().map(|_| ValueCounter::new()).collect();
+    let mut missing = vec![0usize; group_by.len()];
+    let mut group_counts: ValueCounter<Vec<Option<String>>> = ValueCounter::new();
+
+    for entry in entries {
+        total += 1;
+        let values: Vec<Option<String>> = group_by
+            .iter()
+            .map(|field| entry.get_any_field_as_string(field))
+            .collect();
+
+        for (index, value) in values.iter().enumerate() {
+            match value {
+                Some(value) => value_counts[index].add(value.clone()),
+                None => missing[index] += 1,
+            }
+        }
+
+        if group_by.len() > 1 {
+            group_counts.add(values);
+        }
+    }
+
+    let facets = group_by
+        .iter()
+        .zip(value_counts)
+        .zip(missing)
+        .map(|((field, mut counts), missing)| Facet {
+            field: field.clone(),
+            count: total - missing,
+            missing,
+            distinct_count: counts.distinct_count(),
+            approximate: counts.approximate,
+            top: counts
+                .top(top)
+                .into_iter()
+                .map(|(value, count)| ValueCount { value, count })
+                .collect(),
+        })
+        .collect();
+
+    let groups_approximate = group_counts.approximate;
+    let groups = group_counts
+        .top(top)
+        .into_iter()
+        .map(|(values, count)| GroupCount { values, count })
+        .collect();
+
+    AggregateResult {
+        total,
+        facets,
+        groups,
+        groups_approximate,
+    }
+}
+
+/// Counts the values of a field (or combination of fields) in bounded memory.
+///
+/// Every value goes into a HyperLogLog++ sketch for the distinct count, and counts are
+/// kept in a space-saving summary of at most `MAX_TRACKED_VALUES` values: once it is
+/// full, a new value replaces the least counted one and inherits its count. Counts are
+/// exact until then and upper bounds afterwards.
+struct ValueCounter<K: Hash> {
+    distinct: HyperLogLogPlus<K, BuildHasherDefault<DefaultHasher>>,
+    counts: HashMap<K, usize>,
+    /// `counts` ordered by count, to find the value to replace
+    by_count: BTreeSet<(usize, K)>,
+    /// A value was replaced, so counts and the distinct count are estimates
+    approximate: bool,
+}
+
+impl<K: Hash + Eq + Ord + Clone> ValueCounter<K> {
+    fn new() -> Self {
+        Self {
+            distinct: HyperLogLogPlus::new(DISTINCT_PRECISION, BuildHasherDefault::default())
+                .expect("precision is within the supported range"),
+            counts: HashMap::new(),
+            by_count: BTreeSet::new(),
+            approximate: false,
+        }
+    }
+
+    fn add(&mut self, key: K) {
+        self.distinct.insert(&key);
+        let count = if let Some(count) = self.counts.get_mut(&key) {
+            self.by_count.remove(&(*count, key.clone()));
+            *count += 1;
+            *count
+        } else if self.counts.len() < MAX_TRACKED_VALUES {
+            self.counts.insert(key.clone(), 1);
+            1
+        } else {
+            let Some((min, evicted)) = self.by_count.pop_first() else {
+                return;
+            };
+            self.counts.remove(&evicted);
+            self.approximate = true;
+            self.counts.insert(key.clone(), min + 1);
+            min + 1
+        };
+        self.by_count.insert((count, key));
+    }
+
+    fn distinct_count(&mut self) -> usize {
+        if self.approximate {
+            (self.distinct.count().round() as usize).max(MAX_TRACKED_VALUES)
+        } else {
+            self.counts.len()
+        }
+    }
+
+    /// The `n` highest counts, ties broken by value.
+    fn top(self, n: usize) -> Vec<(K, usize)> {
+        let mut counts: Vec<(K, usize)> = self.counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts.truncate(n);
+        counts
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Histogram {
+    pub interval_seconds: i64,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub buckets: Vec<HistogramBucket>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistogramBucket {
+    pub start: DateTime<Utc>,
+    pub total: usize,
+    /// Counts per level name, always listing every level so charts can stack them.
+    pub levels: BTreeMap<&'static str, usize>,
+}
+
+/// Buckets entries by timestamp and level. Empty buckets are kept so gaps in the
+/// stream stay visible. The range defaults to the span of the matched entries.
+pub fn histogram<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    interval: Option<Duration>,
+    range_start: Option<DateTime<Utc>>,
+    range_end: Option<DateTime<Utc>>,
+) -> Result<Histogram> {
+    let entries: Vec<&LogEntry> = entries.into_iter().collect();
+    let Some(layout) = BucketLayout::for_entries(&entries, interval, range_start, range_end)? else {
+        return Ok(Histogram {
+            interval_seconds: interval.map(|i| i.num_seconds()).unwrap_or(1),
+            start: None,
+            end: None,
+            buckets: Vec::new(),
+        });
+    };
+
+    let mut counts = vec![[0usize; LEVELS.len()]; layout.len];
+    for entry in entries {
+        if let Some(bucket) = layout.index_of(entry.timestamp) {
+            let level = LEVELS.iter().position(|l| *l == entry.level).unwrap_or(0);
+            counts[bucket][level] += 1;
+        }
+    }
+
+    let buckets = counts
+        .into_iter()
+        .enumerate()
+        .map(|(index, level_counts)| HistogramBucket {
+            start: layout.bucket_start(index),
+            total: level_counts.iter().sum(),
+            levels: LEVELS
+                .iter()
+                .zip(level_counts)
+                .map(|(level, count)| (level.as_str(), count))
+                .collect(),
+        })
+        .collect();
+
+    Ok(Histogram {
+        interval_seconds: layout.interval_seconds,
+        start: Some(layout.start),
+        end: Some(layout.end),
+        buckets,
+    })
+}
+
+#[derive(Debug, Serialize)]
+pub struct NumericStats {
+    pub count: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub p50: Option<f64>,
+    pub p90: Option<f64>,
+    pub p99: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldStats {
+    pub field: String,
+    /// Matched entries whose field is missing or not numeric.
+    pub non_numeric: usize,
+    #[serde(flatten)]
+    pub stats: NumericStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval_seconds: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub buckets: Option<Vec<NumericBucket>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NumericBucket {
+    pub start: DateTime<Utc>,
+    #[serde(flatten)]
+    pub stats: NumericStats,
+}
+
+impl NumericStats {
+    /// Summarizes the values; percentiles use the nearest-rank method.
+    pub fn from_values(mut values: Vec<f64>) -> Self {
+        if values.is_empty() {
+            return Self {
+                count: 0,
+                min: None,
+                max: None,
+                mean: None,
+                p50: None,
+                p90: None,
+                p99: None,
+            };
+        }
+
+        values.sort_by(|a, b| a.total_cmp(b));
+        let count = values.len();
+        let percentile = |p: f64| {
+            let rank = ((p / 100.0) * count as f64).ceil() as usize;
+            values[rank.clamp(1, count) - 1]
+        };
+
+        Self {
+            count,
+            min: values.first().copied(),
+            max: values.last().copied(),
+            mean: Some(values.iter().sum::<f64>() / count as f64),
+            p50: Some(percentile(50.0)),
+            p90: Some(percentile(90.0)),
+            p99: Some(percentile(99.0)),
+        }
+    }
+}
+
+/// Computes statistics for a numeric dynamic field, optionally bucketed over time.
+/// `bucketing` is `None` for a single summary, `Some(None)` for an automatic interval.
+pub fn field_stats<'a>(
+    entries: impl IntoIterator<Item = &'a LogEntry>,
+    field: &str,
+    bucketing: Option<Option<Duration>>,
+    range_start: Option<DateTime<Utc>>,
+    range_end: Option<DateTime<Utc>>,
+) -> Result<FieldStats> {
+    let mut non_numeric = 0;
+    let mut samples: Vec<(&LogEntry, f64)> = Vec::new();
+    for entry in entries {
+        match entry.get_field_value(field) {
+            Some(DynamicFieldValue::Number(n)) if n.is_finite() => samples.push((entry, *n)),
+            _ => non_numeric += 1,
+        }
+    }
+
+    let (interval_seconds, buckets) = match bucketing {
+        None => (None, None),
+        Some(interval) => {
+            let entries: Vec<&LogEntry> = samples.iter().map(|(entry, _)| *entry).collect();
+            match BucketLayout::for_entries(&entries, interval, range_start, range_end)? {
+                None => (None, Some(Vec::new())),
+                Some(layout) => {
+                    let mut values = vec![Vec::new(); layout.len];
+                    for (entry, value) in &samples {
+                        if let Some(bucket) = layout.index_of(entry.timestamp) {
+                            values[bucket].push(*value);
+                        }
+                    }
+                    let buckets = values
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, values)| NumericBucket {
+                            start: layout.bucket_start(index),
+                            stats: NumericStats::from_values(values),
+                        })
+                        .collect();
+                    (Some(layout.interval_seconds), Some(buckets))
+                }
+            }
+        }
+    };
+
+    Ok(FieldStats {
+        field: field.to_string(),
+        non_numeric,
+        stats: NumericStats::from_values(samples.into_iter().map(|(_, value)| value).collect()),
+        interval_seconds,
+        buckets,
+    })
+}
+
+/// Time buckets aligned to multiples of the interval, so repeated requests line up.
+struct BucketLayout {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    interval_seconds: i64,
+    first_bucket: i64,
+    len: usize,
+}
+
+impl BucketLayout {
+    /// Returns `None` when there is nothing to bucket (no entries and no explicit range).
+    fn for_entries(
+        entries: &[&LogEntry],
+        interval: Option<Duration>,
+        range_start: Option<DateTime<Utc>>,
+        range_end: Option<DateTime<Utc>>,
+    ) -> Result<Option<Self>> {
+        let start = range_start.or_else(|| entries.iter().map(|entry| entry.timestamp).min());
+        let end = range_end.or_else(|| entries.iter().map(|entry| entry.timestamp).max());
+        let (Some(start), Some(end)) = (start, end) else {
+            return Ok(None);
+        };
+        if start > end {
+            return Err(anyhow!("Range start {} is after its end {}", start.to_rfc3339(), end.to_rfc3339()));
+        }
+
+        let span = (end - start).num_seconds().max(1);
+        let interval_seconds = match interval {
+            Some(interval) => interval.num_seconds(),
+            None => auto_interval_seconds(span),
+        };
+        if interval_seconds < 1 {
+            return Err(anyhow!("Interval must be at least one second"));
+        }
+        if span / interval_seconds >= MAX_HISTOGRAM_BUCKETS {
+            return Err(anyhow!(
+                "Interval of {}s would produce more than {} buckets",
+                interval_seconds,
+                MAX_HISTOGRAM_BUCKETS
+            ));
+        }
+
+        let first_bucket = start.timestamp().div_euclid(interval_seconds);
+        let last_bucket = end.timestamp().div_euclid(interval_seconds);
+        Ok(Some(Self {
+            start,
+            end,
+            interval_seconds,
+            first_bucket,
+            len: (last_bucket - first_bucket + 1) as usize,
+        }))
+    }
+
+    fn index_of(&self, timestamp: DateTime<Utc>) -> Option<usize> {
+        if timestamp < self.start || timestamp > self.end {
+            return None;
+        }
+        let index = (timestamp.timestamp().div_euclid(self.interval_seconds) - self.first_bucket) as usize;
+        (index < self.len).then_some(index)
+    }
+
+    fn bucket_start(&self, index: usize) -> DateTime<Utc> {
+        DateTime::from_timestamp((self.first_bucket + index as i64) * self.interval_seconds, 0)
+            .unwrap_or(self.start)
+    }
+}
+
+fn auto_interval_seconds(span_seconds: i64) -> i64 {
+    let raw = (span_seconds + AUTO_HISTOGRAM_BUCKETS - 1) / AUTO_HISTOGRAM_BUCKETS;
+    NICE_INTERVALS
+        .iter()
+        .copied()
+        .find(|step| *step >= raw)
+        .unwrap_or_else(|| (raw + 604_799) / 604_800 * 604_800)
+}
+
+/// Returns the `n` most frequent keys, ties broken by key for stable output.
+/// Splits a comma-separated field list, dropping blanks.
+pub fn parse_field_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogLevel;
+    use chrono::Utc;
+
+    fn entry(level: LogLevel, logger: &str) -> LogEntry {
+        LogEntry::new(1, Utc::now(), level, logger.to_string(), "msg".to_string())
+    }
+
+    #[test]
+    fn test_facets_and_groups() {
+        let entries = vec![
+            entry(LogLevel::Error, "db"),
+            entry(LogLevel::Error, "db"),
+            entry(LogLevel::Error, "http"),
+            entry(LogLevel::Info, "db"),
+        ];
+        let group_by = vec!["logger".to_string(), "level".to_string()];
+        let result = aggregate(&entries, &group_by, 10);
+
+        assert_eq!(result.total, 4);
+        assert_eq!(result.facets[0].distinct_count, 2);
+        assert_eq!(result.facets[0].top[0].value, "db");
+        assert_eq!(result.facets[0].top[0].count, 3);
+        assert_eq!(result.groups[0].values, vec![Some("db".to_string()), Some("ERROR".to_string())]);
+        assert_eq!(result.groups[0].count, 2);
+        assert!(!result.facets[0].approximate);
+    }
+
+    #[test]
+    fn test_high_cardinality_values_are_estimated() {
+        // Five times as many one-off values as are tracked, with a frequent value that
+        // only shows up after the summary is full
+        let distinct = MAX_TRACKED_VALUES * 5;
+        let mut entries: Vec<LogEntry> = (0..distinct)
+            .map(|index| entry(LogLevel::Info, &format!("worker-{index}")))
+            .collect();
+        entries.extend((0..50).map(|_| entry(LogLevel::Info, "late-but-frequent")));
+        let result = aggregate(&entries, &["logger".to_string()], 1);
+
+        let facet = &result.facets[0];
+        assert_eq!(facet.count, distinct + 50);
+        assert!(facet.approximate);
+        let error = (facet.distinct_count as f64 - (distinct + 1) as f64).abs() / distinct as f64;
+        assert!(error < 0.05, "estimated {} distinct values", facet.distinct_count);
+        assert_eq!(facet.top[0].value, "late-but-frequent");
+        assert!(facet.top[0].count >= 50);
+    }
+
+    #[test]
+    fn test_histogram_keeps_gaps() {
+        let base = DateTime::from_timestamp(1_753_963_200, 0).unwrap();
+        let mut first = entry(LogLevel::Info, "db");
+        first.timestamp = base;
+        let mut second = entry(LogLevel::Error, "db");
+        second.timestamp = base + Duration::seconds(25);
+
+        let entries = [first, second];
+        let result = histogram(&entries, Some(Duration::seconds(10)), None, None).unwrap();
+
+        assert_eq!(result.buckets.len(), 3);
+        assert_eq!(result.buckets[0].levels["INFO"], 1);
+        assert_eq!(result.buckets[1].total, 0);
+        assert_eq!(result.buckets[2].levels["ERROR"], 1);
+    }
+
+    #[test]
+    fn test_histogram_rejects_inverted_range() {
+        let start = DateTime::from_timestamp(1_753_963_200, 0).unwrap();
+        let end = start - Duration::days(1);
+        let result = histogram(&[], Some(Duration::seconds(60)), Some(start), Some(end));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_numeric_percentiles() {
+        let stats = NumericStats::from_values((1..=100).map(f64::from).collect());
+
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.min, Some(1.0));
+        assert_eq!(stats.max, Some(100.0));
+        assert_eq!(stats.mean, Some(50.5));
+        assert_eq!(stats.p50, Some(50.0));
+        assert_eq!(stats.p90, Some(90.0));
+        assert_eq!(stats.p99, Some(99.0));
+    }
+}
@@ -0,0 +1,411 @@
+//! Command line interface.
+//!
+//! Without a subcommand the arguments are those of `serve`, so `jsonweblog 8080`
+//! and `jsonweblog --port 8080` keep starting the web viewer. `query` and `export`
+//! read logs from stdin or a file and write matching entries to stdout instead.
+
+use crate::{parser::ParseError, projection::Projection, server::{BindAddress, LogInput, DEFAULT_MAX_ENTRIES}, JsonLogParser, LogEntry, LogLevel, Query};
+use anyhow::{anyhow, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use futures::StreamExt;
+use std::io::Write;
+use std::path::PathBuf;
+
+pub const DEFAULT_PORT: u16 = 3000;
+
+#[derive(Debug, Parser)]
+#[command(name = "jsonweblog", version, about = "Real-time web viewer for JSONL logs")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    serve: ServeArgs,
+}
+
+impl Cli {
+    /// The chosen subcommand, `serve` when none was given.
+    pub fn into_command(self) -> Command {
+        self.command.unwrap_or(Command::Serve(self.serve))
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Serve the web viewer for logs read from stdin or a file (default)
+    Serve(ServeArgs),
+    /// Print the entries matching a query
+    Query(QueryArgs),
+    /// Write entries as JSON Lines, JSON or CSV
+    Export(ExportArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Port to listen on; same as --port
+    #[arg(value_name = "PORT", hide = true, conflicts_with = "port")]
+    legacy_port: Option<u16>,
+    /// Port to listen on
+    #[arg(short, long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+    /// IPv4/IPv6 address or Unix socket path to listen on; 0.0.0.0 or :: listens on
+    /// every interface
+    #[arg(long, value_name = "ADDR", default_value_t = BindAddress::default())]
+    pub bind: BindAddress,
+    /// Fail instead of trying the following ports when the port is in use
+    #[arg(long)]
+    pub no_auto_port: bool,
+    #[command(flatten)]
+    pub input: InputArgs,
+    /// Entries kept in memory; older ones are dropped first
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_ENTRIES, value_parser = parse_max_entries)]
+    pub max_entries: usize,
+    /// Table settings file [default: ./jsonweblog_settings.json]
+    #[arg(long, value_name = "PATH")]
+    pub settings: Option<PathBuf>,
+    /// Open the web interface in the default browser
+    #[arg(long)]
+    pub open_browser: bool,
+    /// Fold consecutive entries with the same message pattern and level
+    #[arg(long)]
+    pub collapse_repeats: bool,
+    /// Fields identifying a trace or request, comma-separated
+    #[arg(long, value_name = "KEYS", value_delimiter = ',')]
+    pub correlation_keys: Option<Vec<String>>,
+    /// JSON file of rules deriving Prometheus metrics from log fields
+    #[arg(long, value_name = "PATH")]
+    pub metric_rules: Option<PathBuf>,
+}
+
+impl ServeArgs {
+    pub fn port(&self) -> u16 {
+        self.legacy_port.unwrap_or(self.port)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// Read logs from this file instead of stdin
+    #[arg(short, long, value_name = "PATH")]
+    pub file: Option<PathBuf>,
+}
+
+impl InputArgs {
+    pub fn log_input(&self) -> LogInput {
+        match &self.file {
+            Some(path) => LogInput::File(path.clone()),
+            None => LogInput::Stdin,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct QueryArgs {
+    /// Query language expression, e.g. 'level>=error AND service=api'
+    #[arg(value_parser = Query::parse)]
+    pub query: Query,
+    #[command(flatten)]
+    pub input: InputArgs,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+    /// Fields to print, comma-separated
+    #[arg(long, value_name = "FIELDS")]
+    pub fields: Option<String>,
+    /// Stop after this many matches
+    #[arg(short = 'n', long, value_name = "N")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Jsonl)]
+    pub format: OutputFormat,
+    /// Fields to export, comma-separated
+    #[arg(long, value_name = "FIELDS")]
+    pub fields: Option<String>,
+    /// Only export entries matching this query
+    #[arg(long, value_name = "QUERY", value_parser = Query::parse)]
+    pub filter: Option<Query>,
+    /// Write to this file instead of stdout
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// One line per entry: time, level, logger and message
+    Text,
+    /// One JSON object per line
+    Jsonl,
+    /// A single JSON array
+    Json,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+fn parse_max_entries(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub async fn run_query(args: QueryArgs) -> Result<()> {
+    let stdout = std::io::stdout();
+    let mut writer = EntryWriter::new(stdout.lock(), args.format, args.fields.as_deref());
+    let limit = args.limit.unwrap_or(usize::MAX);
+    for_each_entry(&args.input.log_input(), |entry| {
+        if writer.count() >= limit {
+            return Ok(false);
+        }
+        if args.query.matches(entry) {
+            writer.write(entry)?;
+        }
+        // Stop reading as soon as the last wanted match is written
+        Ok(writer.count() < limit)
+    })
+    .await?;
+    writer.finish()
+}
+
+pub async fn run_export(args: ExportArgs) -> Result<()> {
+    let out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(std::fs::File::create(path).map_err(|e| anyhow!("Failed to create {}: {}", path.display(), e))?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let mut writer = EntryWriter::new(std::io::BufWriter::new(out), args.format, args.fields.as_deref());
+    for_each_entry(&args.input.log_input(), |entry| {
+        if args.filter.as_ref().is_none_or(|filter| filter.matches(entry)) {
+            writer.write(entry)?;
+        }
+        Ok(true)
+    })
+    .await?;
+    writer.finish()
+}
+
+/// Parses every entry of `input` until `visit` returns `false`. Lines that are not
+/// log entries are skipped; failing to read the input is an error.
+async fn for_each_entry(input: &LogInput, mut visit: impl FnMut(&LogEntry) -> Result<bool>) -> Result<()> {
+    let source = input.source_name();
+    let mut parser = JsonLogParser::new().with_passthrough(false);
+    let mut entries = match input {
+        LogInput::Stdin => parser.parse_stdin().await.boxed_local(),
+        LogInput::File(path) => {
+            let file = tokio::fs::File::open(path)
+                .await
+                .map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+            parser.parse_reader(file).boxed_local()
+        }
+    };
+
+    let mut seq = 0;
+    while let Some(result) = entries.next().await {
+        let mut entry = match result {
+            Ok(entry) => entry,
+            Err(e) if matches!(e.downcast_ref::<ParseError>(), Some(ParseError::Read(_))) => {
+                return Err(anyhow!("Failed to read {}: {}", source, e));
+            }
+            Err(_) => continue,
+        };
+        seq += 1;
+        entry.seq = seq;
+        if !visit(&entry.with_source(source.as_str()))? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Writes entries in an output format, optionally projected to some fields.
+struct EntryWriter<W: Write> {
+    out: W,
+    format: OutputFormat,
+    fields: Option<Vec<String>>,
+    projection: Projection,
+    count: usize,
+}
+
+impl<W: Write> EntryWriter<W> {
+    fn new(out: W, format: OutputFormat, fields: Option<&str>) -> Self {
+        let projection = Projection::new(fields, false);
+        let fields = fields
+            .map(crate::aggregate::parse_field_list)
+            .filter(|fields| !fields.is_empty());
+        Self {
+            out,
+            format,
+            fields,
+            projection,
+            count: 0,
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+
+    fn write(&mut self, entry: &LogEntry) -> Result<()> {
+        match self.format {
+            OutputFormat::Text => match &self.fields {
+                Some(fields) => {
+                    let values: Vec<String> = fields
+                        .iter()
+                        .map(|field| format!("{}={}", field, field_value(entry, field)))
+                        .collect();
+                    writeln!(self.out, "{}", values.join(" "))?;
+                }
+                None => writeln!(
+                    self.out,
+                    "{} {:<5} [{}] {}",
+                    entry.timestamp.to_rfc3339(),
+                    level_name(&entry.level),
+                    entry.logger,
+                    entry.message
+                )?,
+            },
+            OutputFormat::Jsonl => writeln!(self.out, "{}", self.projection.to_json_string(entry)?)?,
+            OutputFormat::Json => {
+                let separator = if self.count == 0 { "[\n" } else { ",\n" };
+                write!(self.out, "{}{}", separator, self.projection.to_json_string(entry)?)?;
+            }
+            OutputFormat::Csv => {
+                let fields = self.fields.get_or_insert_with(|| {
+                    ["timestamp", "level", "logger", "message"].into_iter().map(str::to_string).collect()
+                });
+                if self.count == 0 {
+                    let header: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                    writeln!(self.out, "{}", header.join(","))?;
+                }
+                let row: Vec<String> = fields
+                    .iter()
+                    .map(|field| csv_field(&field_value(entry, field)))
+                    .collect();
+                writeln!(self.out, "{}", row.join(","))?;
+            }
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        if self.format == OutputFormat::Json {
+            let closing = if self.count == 0 { "[]\n" } else { "\n]\n" };
+            self.out.write_all(closing.as_bytes())?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Level as the JSON formats serialize it, e.g. `Warn`, so every format agrees.
+fn level_name(level: &LogLevel) -> String {
+    format!("{:?}", level)
+}
+
+/// Value of `field` as text; empty when the entry lacks it.
+fn field_value(entry: &LogEntry, field: &str) -> String {
+    if field == "level" {
+        return level_name(&entry.level);
+    }
+    entry.get_any_field_as_string(field).unwrap_or_default()
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_commands() {
+        let Command::Serve(serve) = Cli::try_parse_from(["jsonweblog", "8080", "--collapse-repeats"]).unwrap().into_command() else {
+            panic!("serving is the default command");
+        };
+        assert_eq!(serve.port(), 8080);
+        assert!(serve.collapse_repeats);
+
+        let Command::Serve(serve) = Cli::try_parse_from(["jsonweblog", "serve", "--correlation-keys", "trace_id,req.id"])
+            .unwrap()
+            .into_command()
+        else {
+            panic!("expected serve");
+        };
+        assert_eq!(serve.port(), DEFAULT_PORT);
+        assert_eq!(serve.bind, BindAddress::default());
+        assert_eq!(serve.correlation_keys.unwrap(), ["trace_id", "req.id"]);
+
+        let Command::Query(query) = Cli::try_parse_from(["jsonweblog", "query", "level>=error", "-f", "app.log", "-n", "5"])
+            .unwrap()
+            .into_command()
+        else {
+            panic!("expected query");
+        };
+        assert_eq!(query.query.as_str(), "level>=error");
+        assert_eq!(query.limit, Some(5));
+        assert_eq!(query.input.log_input(), LogInput::File("app.log".into()));
+
+        assert!(Cli::try_parse_from(["jsonweblog", "query", "level>="]).is_err());
+        assert!(Cli::try_parse_from(["jsonweblog", "--max-entries", "0"]).is_err());
+        assert!(Cli::try_parse_from(["jsonweblog", "--port", "70000"]).is_err());
+        assert!(Cli::try_parse_from(["jsonweblog", "8080", "--port", "9090"]).is_err());
+        assert!(Cli::try_parse_from(["jsonweblog", "--bind", "not-an-address"]).is_err());
+    }
+
+    #[test]
+    fn test_help_query_example_matches() {
+        use clap::CommandFactory;
+
+        let command = Cli::command();
+        let query_command = command.find_subcommand("query").unwrap();
+        let help = query_command
+            .get_arguments()
+            .find(|arg| arg.get_id() == "query")
+            .and_then(|arg| arg.get_help())
+            .unwrap()
+            .to_string();
+        let example = help.split('\'').nth(1).expect("help shows a quoted example");
+
+        let Command::Query(query) = Cli::try_parse_from(["jsonweblog", "query", example]).unwrap().into_command() else {
+            panic!("expected query");
+        };
+        let parser = JsonLogParser::new();
+        let hit = parser.parse_line(r#"{"level": "error", "service": "api", "message": "failed"}"#, 1).unwrap();
+        let miss = parser.parse_line(r#"{"level": "error", "service": "db", "message": "failed"}"#, 2).unwrap();
+        assert!(query.query.matches(&hit));
+        assert!(!query.query.matches(&miss));
+    }
+
+    #[test]
+    fn test_writes_csv_and_json() {
+        let parser = JsonLogParser::new();
+        let entry = parser
+            .parse_line(r#"{"level": "warn", "logger": "api", "message": "slow, \"very\" slow", "ms": 900}"#, 1)
+            .unwrap();
+
+        let mut csv = EntryWriter::new(Vec::new(), OutputFormat::Csv, Some("level,message,ms"));
+        csv.write(&entry).unwrap();
+        let text = String::from_utf8(csv.out.clone()).unwrap();
+        assert_eq!(text, "level,message,ms\nWarn,\"slow, \"\"very\"\" slow\",900\n");
+
+        let mut buffer = Vec::new();
+        let mut json = EntryWriter::new(&mut buffer, OutputFormat::Json, Some("level,ms"));
+        json.write(&entry).unwrap();
+        json.write(&entry).unwrap();
+        json.finish().unwrap();
+        let entries: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(entries, serde_json::json!([{"level": "Warn", "ms": 900}, {"level": "Warn", "ms": 900}]));
+    }
+}
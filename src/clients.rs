@@ -0,0 +1,185 @@
+//! Registry of connected streaming clients (WebSocket and Server-Sent Events).
+//!
+//! Each connection registers when it starts and gets a `ClientHandle`; dropping the
+//! handle removes the client, so the registry stays accurate however the connection
+//! ends. The handle also records what the client is subscribed to and how far it has
+//! read, which `/api/clients` reports.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ClientKind {
+    #[serde(rename = "websocket")]
+    WebSocket,
+    #[serde(rename = "sse")]
+    EventStream,
+}
+
+#[derive(Debug)]
+struct Client {
+    id: u64,
+    kind: ClientKind,
+    remote_addr: Option<SocketAddr>,
+    connected_at: DateTime<Utc>,
+    filter: Mutex<serde_json::Value>,
+    last_seen_seq: AtomicU64,
+    sent: AtomicU64,
+    skipped: AtomicU64,
+}
+
+/// Point-in-time view of a client.
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientInfo {
+    pub id: u64,
+    pub kind: ClientKind,
+    pub remote_addr: Option<SocketAddr>,
+    pub connected_at: DateTime<Utc>,
+    /// Query parameters of the active subscription
+    pub filter: serde_json::Value,
+    /// Highest sequence ID the client's stream has reached
+    pub last_seen_seq: u64,
+    /// Entries ingested that the stream has not reached yet
+    pub lag: u64,
+    pub sent: u64,
+    /// Live entries skipped because the client fell behind, later resent from the buffer
+    pub skipped: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ClientRegistry {
+    next_id: AtomicU64,
+    clients: Mutex<BTreeMap<u64, Arc<Client>>>,
+}
+
+impl ClientRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(
+        self: &Arc<Self>,
+        kind: ClientKind,
+        remote_addr: Option<SocketAddr>,
+        filter: serde_json::Value,
+    ) -> ClientHandle {
+        let client = Arc::new(Client {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed) + 1,
+            kind,
+            remote_addr,
+            connected_at: Utc::now(),
+            filter: Mutex::new(filter),
+            last_seen_seq: AtomicU64::new(0),
+            sent: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+        });
+        self.lock().insert(client.id, Arc::clone(&client));
+        ClientHandle {
+            registry: Arc::clone(self),
+            client,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Clients in connection order; `latest_seq` is the newest sequence ID assigned.
+    pub fn list(&self, latest_seq: u64) -> Vec<ClientInfo> {
+        self.lock()
+            .values()
+            .map(|client| {
+                let last_seen_seq = client.last_seen_seq.load(Ordering::Relaxed);
+                ClientInfo {
+                    id: client.id,
+                    kind: client.kind,
+                    remote_addr: client.remote_addr,
+                    connected_at: client.connected_at,
+                    filter: client.filter.lock().map(|filter| filter.clone()).unwrap_or_default(),
+                    last_seen_seq,
+                    lag: latest_seq.saturating_sub(last_seen_seq),
+                    sent: client.sent.load(Ordering::Relaxed),
+                    skipped: client.skipped.load(Ordering::Relaxed),
+                }
+            })
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<u64, Arc<Client>>> {
+        // The map stays consistent even if a holder panicked
+        self.clients.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Registration of one connection; the client is removed when this is dropped.
+#[derive(Debug)]
+pub struct ClientHandle {
+    registry: Arc<ClientRegistry>,
+    client: Arc<Client>,
+}
+
+impl ClientHandle {
+    pub fn id(&self) -> u64 {
+        self.client.id
+    }
+
+    pub fn set_filter(&self, filter: serde_json::Value) {
+        if let Ok(mut current) = self.client.filter.lock() {
+            *current = filter;
+        }
+    }
+
+    pub fn record_seen(&self, seq: u64) {
+        self.client.last_seen_seq.fetch_max(seq, Ordering::Relaxed);
+    }
+
+    pub fn record_sent(&self) {
+        self.client.sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_skipped(&self, skipped: u64) {
+        self.client.skipped.fetch_add(skipped, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ClientHandle {
+    fn drop(&mut self) {
+        self.registry.lock().remove(&self.client.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handles_unregister_on_drop() {
+        let registry = Arc::new(ClientRegistry::new());
+        let first = registry.register(ClientKind::WebSocket, None, serde_json::json!({"q": "level>=warn"}));
+        let second = registry.register(ClientKind::EventStream, "127.0.0.1:5000".parse().ok(), serde_json::json!({}));
+        assert_ne!(first.id(), second.id());
+
+        first.record_seen(40);
+        first.record_sent();
+        first.record_skipped(7);
+        let clients = registry.list(100);
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients[0].lag, 60);
+        assert_eq!(clients[0].sent, 1);
+        assert_eq!(clients[0].skipped, 7);
+        assert_eq!(clients[0].filter["q"], "level>=warn");
+
+        drop(first);
+        assert_eq!(registry.len(), 1);
+        drop(second);
+        assert!(registry.is_empty());
+    }
+}
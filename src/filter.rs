@@ -1,15 +1,195 @@
-use crate::{LogEntry, LogLevel};
+use crate::{query::Query, LogEntry, LogLevel};
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use lru::LruCache;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, OnceLock};
+
+/// Maximum number of compiled patterns kept in the process-wide regex cache; the least
+/// recently used pattern is dropped first.
+const REGEX_CACHE_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    Exact,
+    Prefix,
+    #[default]
+    Substring,
+    Regex,
+    Glob,
+}
+
+/// A text predicate with an explicit match mode and case sensitivity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "TextMatcherSpec")]
+pub struct TextMatcher {
+    pub pattern: String,
+    pub mode: MatchMode,
+    pub case_sensitive: bool,
+    #[serde(skip)]
+    folded: String,
+    #[serde(skip)]
+    regex: Option<Regex>,
+}
+
+#[derive(Deserialize)]
+struct TextMatcherSpec {
+    pattern: String,
+    #[serde(default)]
+    mode: MatchMode,
+    #[serde(default)]
+    case_sensitive: bool,
+}
+
+impl TextMatcher {
+    pub fn new(pattern: String, mode: MatchMode, case_sensitive: bool) -> Result<Self> {
+        let regex = match mode {
+            MatchMode::Regex => Some(cached_regex(&pattern, case_sensitive)?),
+            MatchMode::Glob => Some(cached_regex(&glob_to_regex(&pattern), case_sensitive)?),
+            _ => None,
+        };
+        let folded = if case_sensitive {
+            pattern.clone()
+        } else {
+            pattern.to_lowercase()
+        };
+
+        Ok(Self {
+            pattern,
+            mode,
+            case_sensitive,
+            folded,
+            regex,
+        })
+    }
+
+    /// Plain substring matcher; never fails because no pattern is compiled.
+    pub fn substring(pattern: String, case_sensitive: bool) -> Self {
+        Self::new(pattern, MatchMode::Substring, case_sensitive)
+            .expect("substring matchers need no compilation")
+    }
+
+    pub fn matches(&self, text: &str) -> bool {
+        if let Some(regex) = &self.regex {
+            return regex.is_match(text);
+        }
+
+        let lowered;
+        let text = if self.case_sensitive {
+            text
+        } else {
+            lowered = text.to_lowercase();
+            &lowered
+        };
+
+        match self.mode {
+            MatchMode::Exact => text == self.folded,
+            MatchMode::Prefix => text.starts_with(&self.folded),
+            _ => text.contains(&self.folded),
+        }
+    }
+}
+
+impl TryFrom<TextMatcherSpec> for TextMatcher {
+    type Error = anyhow::Error;
+
+    fn try_from(spec: TextMatcherSpec) -> Result<Self> {
+        Self::new(spec.pattern, spec.mode, spec.case_sensitive)
+    }
+}
+
+/// Compiles a regex, reusing earlier compilations of the same pattern across requests.
+fn cached_regex(pattern: &str, case_sensitive: bool) -> Result<Regex> {
+    static CACHE: OnceLock<Mutex<LruCache<(String, bool), Regex>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| {
+        Mutex::new(LruCache::new(NonZeroUsize::new(REGEX_CACHE_CAPACITY).expect("capacity is non-zero")))
+    });
+
+    let key = (pattern.to_string(), case_sensitive);
+    if let Some(regex) = cache.lock().unwrap().get(&key) {
+        return Ok(regex.clone());
+    }
+
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| anyhow!("Invalid pattern '{}': {}", pattern, e))?;
+
+    cache.lock().unwrap().put(key, regex.clone());
+    Ok(regex)
+}
+
+/// Translates a glob (`*`, `?`, `[...]`, `[!...]`) into an anchored regex.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut in_class = false;
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if !in_class => regex.push_str(".*"),
+            '?' if !in_class => regex.push('.'),
+            '[' if !in_class => {
+                in_class = true;
+                regex.push('[');
+                if chars.next_if_eq(&'!').is_some() {
+                    regex.push('^');
+                }
+            }
+            ']' if in_class => {
+                in_class = false;
+                regex.push(']');
+            }
+            c if in_class => regex.push(c),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// A matcher as written by current clients, or the plain string older filters used.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MatcherOrString {
+    Plain(String),
+    Matcher(TextMatcher),
+}
+
+/// Reads a plain string as the case-insensitive substring search it used to mean.
+fn search_text_compat<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<TextMatcher>, D::Error> {
+    matcher_compat(deserializer, false)
+}
+
+/// Reads a plain string as the case-sensitive substring filter it used to mean.
+fn name_filter_compat<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<TextMatcher>, D::Error> {
+    matcher_compat(deserializer, true)
+}
+
+fn matcher_compat<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    case_sensitive: bool,
+) -> Result<Option<TextMatcher>, D::Error> {
+    Ok(Option::<MatcherOrString>::deserialize(deserializer)?.map(|value| match value {
+        MatcherOrString::Plain(pattern) => TextMatcher::substring(pattern, case_sensitive),
+        MatcherOrString::Matcher(matcher) => matcher,
+    }))
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogFilter {
     pub level: Option<LogLevel>,
-    pub search_text: Option<String>,
-    pub logger: Option<String>,
-    pub module: Option<String>,
+    #[serde(default, deserialize_with = "search_text_compat")]
+    pub search_text: Option<TextMatcher>,
+    #[serde(default, deserialize_with = "name_filter_compat")]
+    pub logger: Option<TextMatcher>,
+    #[serde(default, deserialize_with = "name_filter_compat")]
+    pub module: Option<TextMatcher>,
     pub start_time: Option<DateTime<Utc>>,
     pub end_time: Option<DateTime<Utc>>,
+    pub query: Option<Query>,
 }
 
 impl LogFilter {
@@ -21,6 +201,7 @@ impl LogFilter {
             module: None,
             start_time: None,
             end_time: None,
+            query: None,
         }
     }
 
@@ -30,17 +211,32 @@ impl LogFilter {
     }
 
     pub fn with_search_text(mut self, text: String) -> Self {
-        self.search_text = Some(text);
+        self.search_text = Some(TextMatcher::substring(text, false));
+        self
+    }
+
+    pub fn with_search_matcher(mut self, matcher: TextMatcher) -> Self {
+        self.search_text = Some(matcher);
         self
     }
 
     pub fn with_logger(mut self, logger: String) -> Self {
-        self.logger = Some(logger);
+        self.logger = Some(TextMatcher::substring(logger, true));
+        self
+    }
+
+    pub fn with_logger_matcher(mut self, matcher: TextMatcher) -> Self {
+        self.logger = Some(matcher);
         self
     }
 
     pub fn with_module(mut self, module: String) -> Self {
-        self.module = Some(module);
+        self.module = Some(TextMatcher::substring(module, true));
+        self
+    }
+
+    pub fn with_module_matcher(mut self, matcher: TextMatcher) -> Self {
+        self.module = Some(matcher);
         self
     }
 
@@ -50,6 +246,21 @@ impl LogFilter {
         self
     }
 
+    pub fn with_start_time(mut self, start: DateTime<Utc>) -> Self {
+        self.start_time = Some(start);
+        self
+    }
+
+    pub fn with_end_time(mut self, end: DateTime<Utc>) -> Self {
+        self.end_time = Some(end);
+        self
+    }
+
+    pub fn with_query(mut self, query: Query) -> Self {
+        self.query = Some(query);
+        self
+    }
+
     pub fn matches(&self, entry: &LogEntry) -> bool {
         // Level filter
         if let Some(filter_level) = &self.level {
@@ -58,28 +269,32 @@ impl LogFilter {
             }
         }
 
-        // Search text filter (case-insensitive)
+        // Search text filter
         if let Some(search_text) = &self.search_text {
-            let search_lower = search_text.to_lowercase();
-            let message_matches = entry.message.to_lowercase().contains(&search_lower);
-            let logger_matches = entry.logger.to_lowercase().contains(&search_lower);
+            let message_matches = search_text.matches(&entry.message);
+            let logger_matches = search_text.matches(&entry.logger);
             let module_matches = entry.module
                 .as_ref()
-                .map(|m| m.to_lowercase().contains(&search_lower))
+                .map(|m| search_text.matches(m))
                 .unwrap_or(false);
             let function_matches = entry.function
                 .as_ref()
-                .map(|f| f.to_lowercase().contains(&search_lower))
+                .map(|f| search_text.matches(f))
                 .unwrap_or(false);
 
-            if !message_matches && !logger_matches && !module_matches && !function_matches {
+            if !message_matches
+                && !logger_matches
+                && !module_matches
+                && !function_matches
+                && !entry.scalar_field_strings().any(|value| search_text.matches(&value))
+            {
                 return false;
             }
         }
 
         // Logger filter
         if let Some(filter_logger) = &self.logger {
-            if !entry.logger.contains(filter_logger) {
+            if !filter_logger.matches(&entry.logger) {
                 return false;
             }
         }
@@ -87,7 +302,7 @@ impl LogFilter {
         // Module filter
         if let Some(filter_module) = &self.module {
             if let Some(entry_module) = &entry.module {
-                if !entry_module.contains(filter_module) {
+                if !filter_module.matches(entry_module) {
                     return false;
                 }
             } else {
@@ -108,6 +323,13 @@ impl LogFilter {
             }
         }
 
+        // Query expression filter
+        if let Some(query) = &self.query {
+            if !query.matches(entry) {
+                return false;
+            }
+        }
+
         true
     }
 
@@ -118,6 +340,7 @@ impl LogFilter {
             && self.module.is_none()
             && self.start_time.is_none()
             && self.end_time.is_none()
+            && self.query.is_none()
     }
 
     pub fn clear(&mut self) {
@@ -127,6 +350,7 @@ impl LogFilter {
         self.module = None;
         self.start_time = None;
         self.end_time = None;
+        self.query = None;
     }
 }
 
@@ -202,4 +426,54 @@ mod tests {
         assert!(filter.matches(&matching_entry));
         assert!(!filter.matches(&non_matching_entry));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_match_modes() {
+        let logger = |name: &str| {
+            LogEntry::new(1, Utc::now(), LogLevel::Info, name.to_string(), "msg".to_string())
+        };
+
+        let exact = LogFilter::new()
+            .with_logger_matcher(TextMatcher::new("db".to_string(), MatchMode::Exact, true).unwrap());
+        assert!(exact.matches(&logger("db")));
+        assert!(!exact.matches(&logger("db.pool")));
+
+        let glob = LogFilter::new()
+            .with_logger_matcher(TextMatcher::new("APP.*.handler".to_string(), MatchMode::Glob, false).unwrap());
+        assert!(glob.matches(&logger("app.http.handler")));
+        assert!(!glob.matches(&logger("app.http.handler2")));
+
+        let regex = LogFilter::new()
+            .with_logger_matcher(TextMatcher::new("^worker-[0-9]+$".to_string(), MatchMode::Regex, true).unwrap());
+        assert!(regex.matches(&logger("worker-12")));
+        assert!(!regex.matches(&logger("Worker-12")));
+    }
+
+    #[test]
+    fn test_negated_glob_class() {
+        let matcher = TextMatcher::new("worker-[!0-9]*".to_string(), MatchMode::Glob, true).unwrap();
+        assert!(matcher.matches("worker-main"));
+        assert!(!matcher.matches("worker-12"));
+    }
+
+    #[test]
+    fn test_deserializes_legacy_string_matchers() {
+        let filter: LogFilter = serde_json::from_str(
+            r#"{"search_text": "ERROR", "logger": {"pattern": "db.*", "mode": "glob"}, "module": "net"}"#,
+        )
+        .unwrap();
+        let entry = LogEntry::new(1, Utc::now(), LogLevel::Info, "db.pool".to_string(), "an error".to_string())
+            .with_module("net::tcp".to_string());
+        assert!(filter.matches(&entry));
+        assert!(filter.module.unwrap().case_sensitive);
+
+        let empty: LogFilter = serde_json::from_str("{}").unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_regex_is_rejected() {
+        let result = TextMatcher::new("(unclosed".to_string(), MatchMode::Regex, false);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! Fingerprinting of log messages and grouping of warnings and errors by fingerprint.
+//!
+//! Messages are normalized by masking the parts that vary between occurrences of the
+//! same problem (quoted strings, UUIDs, IP addresses, hex and decimal numbers), so
+//! `"timeout after 503ms for user 'bob'"` and `"timeout after 12ms for user 'eve'"`
+//! share the template `timeout after <num>ms for user <str>` and one fingerprint.
+
+use crate::{LogEntry, LogLevel};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+/// Groups kept before the least recently seen ones are evicted.
+const MAX_GROUPS: usize = 10_000;
+
+struct Masks {
+    quoted: Regex,
+    uuid: Regex,
+    ip: Regex,
+    hex: Regex,
+    number: Regex,
+}
+
+fn masks() -> &'static Masks {
+    static MASKS: OnceLock<Masks> = OnceLock::new();
+    MASKS.get_or_init(|| Masks {
+        quoted: Regex::new(r#""[^"]*"|'[^']*'"#).expect("valid quoted-string pattern"),
+        uuid: Regex::new(r"(?i)\b[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}\b")
+            .expect("valid UUID pattern"),
+        ip: Regex::new(
+            r"(?i)\b\d{1,3}(?:\.\d{1,3}){3}(?::\d+)?\b|\b(?:[0-9a-f]{1,4}:){3,7}[0-9a-f]{1,4}\b",
+        )
+        .expect("valid IP pattern"),
+        hex: Regex::new(r"(?i)\b0x[0-9a-f]+\b|\b[0-9a-f]{12,}\b").expect("valid hex pattern"),
+        number: Regex::new(r"[-+]?\d+(?:\.\d+)?").expect("valid number pattern"),
+    })
+}
+
+/// Masks the variable parts of a message, leaving its template.
+pub fn normalize_message(message: &str) -> String {
+    let masks = masks();
+    let text = masks.quoted.replace_all(message, "<str>");
+    let text = masks.uuid.replace_all(&text, "<uuid>");
+    let text = masks.ip.replace_all(&text, "<ip>");
+    let text = masks.hex.replace_all(&text, "<hex>");
+    masks.number.replace_all(&text, "<num>").into_owned()
+}
+
+/// Stable 64-bit FNV-1a hash of the normalized message, as 16 hex digits.
+pub fn fingerprint(message: &str) -> String {
+    fingerprint_template(&normalize_message(message))
+}
+
+/// Like `fingerprint`, for a message that has already been normalized.
+pub fn fingerprint_template(template: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in template.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorGroup {
+    pub fingerprint: String,
+    pub template: String,
+    /// First message seen with this fingerprint
+    pub example: String,
+    /// Highest level seen in the group
+    pub level: LogLevel,
+    pub count: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub last_seq: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupSort {
+    #[default]
+    Count,
+    LastSeen,
+    FirstSeen,
+}
+
+/// Warning and error groups collected from every ingested entry, independent of the
+/// bounded log buffer so counts cover the whole session.
+#[derive(Debug, Default)]
+pub struct ErrorGroups {
+    groups: HashMap<String, ErrorGroup>,
+    /// `(last_seq, fingerprint)` of every group, so the stalest one is found without a scan
+    by_last_seq: BTreeSet<(u64, String)>,
+}
+
+impl ErrorGroups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only warnings and above are grouped; `entry.fingerprint` must already be set.
+    pub fn record(&mut self, entry: &LogEntry) {
+        if entry.level < LogLevel::Warn || entry.fingerprint.is_empty() {
+            return;
+        }
+
+        if let Some(group) = self.groups.get_mut(&entry.fingerprint) {
+            group.count += 1;
+            group.level = group.level.clone().max(entry.level.clone());
+            group.first_seen = group.first_seen.min(entry.timestamp);
+            group.last_seen = group.last_seen.max(entry.timestamp);
+            if group.last_seq != entry.seq {
+                self.by_last_seq.remove(&(group.last_seq, entry.fingerprint.clone()));
+                self.by_last_seq.insert((entry.seq, entry.fingerprint.clone()));
+                group.last_seq = entry.seq;
+            }
+            return;
+        }
+
+        if self.groups.len() >= MAX_GROUPS {
+            if let Some((_, stale)) = self.by_last_seq.pop_first() {
+                self.groups.remove(&stale);
+            }
+        }
+        self.by_last_seq.insert((entry.seq, entry.fingerprint.clone()));
+        self.groups.insert(
+            entry.fingerprint.clone(),
+            ErrorGroup {
+                fingerprint: entry.fingerprint.clone(),
+                template: normalize_message(&entry.message),
+                example: entry.message.clone(),
+                level: entry.level.clone(),
+                count: 1,
+                first_seen: entry.timestamp,
+                last_seen: entry.timestamp,
+                last_seq: entry.seq,
+            },
+        );
+    }
+
+    /// Groups at or above `min_level`, sorted descending by `sort`.
+    pub fn list(&self, min_level: &LogLevel, sort: GroupSort, limit: usize) -> Vec<ErrorGroup> {
+        let mut groups: Vec<&ErrorGroup> =
+            self.groups.values().filter(|group| &group.level >= min_level).collect();
+        groups.sort_by(|a, b| match sort {
+            GroupSort::Count => b.count.cmp(&a.count).then(b.last_seq.cmp(&a.last_seq)),
+            GroupSort::LastSeen => b.last_seen.cmp(&a.last_seen),
+            GroupSort::FirstSeen => b.first_seen.cmp(&a.first_seen),
+        });
+        groups.into_iter().take(limit).cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.groups.clear();
+        self.by_last_seq.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_message() {
+        assert_eq!(
+            normalize_message("timeout after 503ms for user 'bob' from 10.0.0.12:8080"),
+            "timeout after <num>ms for user <str> from <ip>"
+        );
+        assert_eq!(
+            normalize_message("order 3f2b8c1e-9a4d-4c2e-8f1a-0b6d5e4c3a21 failed: \"out of stock\""),
+            "order <uuid> failed: <str>"
+        );
+        assert_eq!(normalize_message("bad pointer 0xdeadbeef"), "bad pointer <hex>");
+        assert_eq!(fingerprint("retry 1 of 5"), fingerprint("retry 4 of 5"));
+        assert_ne!(fingerprint("retry 1 of 5"), fingerprint("gave up after 5"));
+    }
+
+    #[test]
+    fn test_groups_track_counts() {
+        let mut groups = ErrorGroups::new();
+        for (seq, (level, message)) in [
+            (LogLevel::Error, "db timeout after 30s"),
+            (LogLevel::Info, "request 1 served"),
+            (LogLevel::Error, "db timeout after 45s"),
+            (LogLevel::Warn, "disk 91% full"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut entry = LogEntry::new(seq as u64, Utc::now(), level, "app".to_string(), message.to_string());
+            entry.seq = seq as u64 + 1;
+            entry.fingerprint = fingerprint(message);
+            groups.record(&entry);
+        }
+
+        assert_eq!(groups.len(), 2);
+        let listed = groups.list(&LogLevel::Warn, GroupSort::Count, 10);
+        assert_eq!(listed[0].template, "db timeout after <num>s");
+        assert_eq!(listed[0].count, 2);
+        assert_eq!(listed[0].example, "db timeout after 30s");
+        assert_eq!(listed[0].last_seq, 3);
+        assert_eq!(groups.list(&LogLevel::Error, GroupSort::Count, 10).len(), 1);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_seen_group() {
+        let mut groups = ErrorGroups::new();
+        let mut record = |seq: u64, fingerprint: &str| {
+            let mut entry = LogEntry::new(seq, Utc::now(), LogLevel::Error, "app".to_string(), "failed".to_string());
+            entry.seq = seq;
+            entry.fingerprint = fingerprint.to_string();
+            groups.record(&entry);
+        };
+        record(1, "first");
+        for index in 1..MAX_GROUPS {
+            record(index as u64 + 1, &format!("group-{index}"));
+        }
+        // Seeing "first" again makes group-1 the stalest
+        record(MAX_GROUPS as u64 + 1, "first");
+        record(MAX_GROUPS as u64 + 2, "new");
+
+        assert_eq!(groups.len(), MAX_GROUPS);
+        assert!(groups.groups.contains_key("first"));
+        assert!(groups.groups.contains_key("new"));
+        assert!(!groups.groups.contains_key("group-1"));
+        assert_eq!(groups.by_last_seq.len(), MAX_GROUPS);
+    }
+}
@@ -4,8 +4,22 @@ pub mod server;
 pub mod filter;
 pub mod ui;
 pub mod schema;
+pub mod query;
+pub mod time_range;
+pub mod projection;
+pub mod aggregate;
+pub mod grouping;
+pub mod patterns;
+pub mod trace;
+pub mod clients;
+pub mod metrics;
+pub mod metric_rules;
+pub mod wire;
+pub mod websocket;
+pub mod cli;
 
 pub use log_entry::{LogEntry, LogLevel};
 pub use parser::JsonLogParser;
 pub use server::WebServer;
-pub use filter::LogFilter;
\ No newline at end of file
+pub use filter::LogFilter;
+pub use query::Query;
\ No newline at end of file
@@ -1,9 +1,13 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
+    /// Monotonic sequence ID assigned by the server when the entry is stored.
+    #[serde(default)]
+    pub seq: u64,
     pub line: u64,
     pub timestamp: DateTime<Utc>,
     pub level: LogLevel,
@@ -11,10 +15,46 @@ pub struct LogEntry {
     pub message: String,
     pub module: Option<String>,
     pub function: Option<String>,
+    /// Input the entry was read from, e.g. `stdin`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub source: String,
+    /// Hash of the message with numbers, IDs and quoted strings masked (see `grouping`).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub fingerprint: String,
+    /// ID of the message template learned by `patterns::PatternMiner`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern_id: Option<u64>,
+    /// Set when consecutive entries with the same fingerprint were folded into this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<RepeatInfo>,
     pub raw_fields: HashMap<String, serde_json::Value>,
+    /// Flattened view of `raw_fields`; not serialized by default because it duplicates
+    /// them (see `projection::Projection` for opting in).
+    #[serde(skip_serializing, default)]
     pub dynamic_fields: HashMap<String, DynamicFieldValue>,
 }
 
+/// Folded entries remembered per repeated entry so the group can be expanded.
+pub const MAX_REPEAT_SAMPLES: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepeatInfo {
+    /// Total number of entries in the group, including the first one
+    pub count: u64,
+    pub first_timestamp: DateTime<Utc>,
+    pub last_timestamp: DateTime<Utc>,
+    pub last_line: u64,
+    /// The first folded entries after the original one
+    pub samples: Vec<RepeatSample>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepeatSample {
+    pub line: u64,
+    pub timestamp: DateTime<Utc>,
+    pub message: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DynamicFieldValue {
     String(String),
@@ -25,7 +65,26 @@ pub enum DynamicFieldValue {
     Null,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+impl DynamicFieldValue {
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            DynamicFieldValue::String(s) => serde_json::Value::String(s.clone()),
+            // Integral values were integers in the source JSON, so keep them that way
+            DynamicFieldValue::Number(n) if n.fract() == 0.0 && n.abs() < 9.0e15 => {
+                serde_json::Value::from(*n as i64)
+            }
+            DynamicFieldValue::Number(n) => serde_json::Number::from_f64(*n)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            DynamicFieldValue::Boolean(b) => serde_json::Value::Bool(*b),
+            DynamicFieldValue::Object(obj) => obj.clone(),
+            DynamicFieldValue::Array(arr) => serde_json::Value::Array(arr.clone()),
+            DynamicFieldValue::Null => serde_json::Value::Null,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum LogLevel {
     Trace,
     Debug,
@@ -36,15 +95,21 @@ pub enum LogLevel {
 }
 
 impl LogLevel {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
+        Self::parse(s).unwrap_or(LogLevel::Info)
+    }
+
+    /// Strict variant of `from_str` that rejects unknown level names.
+    pub fn parse(s: &str) -> Option<Self> {
         match s.to_uppercase().as_str() {
-            "TRACE" => LogLevel::Trace,
-            "DEBUG" => LogLevel::Debug,
-            "INFO" => LogLevel::Info,
-            "WARN" | "WARNING" => LogLevel::Warn,
-            "ERROR" => LogLevel::Error,
-            "FATAL" | "CRITICAL" => LogLevel::Fatal,
-            _ => LogLevel::Info,
+            "TRACE" => Some(LogLevel::Trace),
+            "DEBUG" => Some(LogLevel::Debug),
+            "INFO" => Some(LogLevel::Info),
+            "WARN" | "WARNING" => Some(LogLevel::Warn),
+            "ERROR" => Some(LogLevel::Error),
+            "FATAL" | "CRITICAL" => Some(LogLevel::Fatal),
+            _ => None,
         }
     }
 
@@ -80,6 +145,7 @@ impl LogEntry {
         message: String,
     ) -> Self {
         Self {
+            seq: 0,
             line,
             timestamp,
             level,
@@ -87,11 +153,42 @@ impl LogEntry {
             message,
             module: None,
             function: None,
+            source: String::new(),
+            fingerprint: String::new(),
+            pattern_id: None,
+            repeat: None,
             raw_fields: HashMap::new(),
             dynamic_fields: HashMap::new(),
         }
     }
 
+    /// Folds a consecutive repeat of this entry into it.
+    pub fn fold_repeat(&mut self, other: &LogEntry) {
+        let repeat = self.repeat.get_or_insert_with(|| RepeatInfo {
+            count: 1,
+            first_timestamp: self.timestamp,
+            last_timestamp: self.timestamp,
+            last_line: self.line,
+            samples: Vec::new(),
+        });
+        repeat.count += 1;
+        repeat.first_timestamp = repeat.first_timestamp.min(other.timestamp);
+        repeat.last_timestamp = repeat.last_timestamp.max(other.timestamp);
+        repeat.last_line = other.line;
+        if repeat.samples.len() < MAX_REPEAT_SAMPLES {
+            repeat.samples.push(RepeatSample {
+                line: other.line,
+                timestamp: other.timestamp,
+                message: other.message.clone(),
+            });
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = source.into();
+        self
+    }
+
     pub fn with_module(mut self, module: String) -> Self {
         self.module = Some(module);
         self
@@ -184,4 +281,32 @@ impl LogEntry {
             DynamicFieldValue::Array(arr) => serde_json::to_string(arr).unwrap_or_default(),
         })
     }
-}
\ No newline at end of file
+
+    /// Scalar dynamic field values as strings, for free-text search across all fields.
+    pub fn scalar_field_strings(&self) -> impl Iterator<Item = Cow<'_, str>> + '_ {
+        self.dynamic_fields.values().filter_map(|value| match value {
+            DynamicFieldValue::String(s) => Some(Cow::Borrowed(s.as_str())),
+            DynamicFieldValue::Number(n) => Some(Cow::Owned(n.to_string())),
+            DynamicFieldValue::Boolean(b) => Some(Cow::Owned(b.to_string())),
+            DynamicFieldValue::Null | DynamicFieldValue::Object(_) | DynamicFieldValue::Array(_) => None,
+        })
+    }
+
+    /// Like `get_field_as_string`, but resolves the built-in entry fields first.
+    pub fn get_any_field_as_string(&self, field_path: &str) -> Option<String> {
+        match field_path {
+            "seq" => Some(self.seq.to_string()),
+            "line" => Some(self.line.to_string()),
+            "timestamp" => Some(self.timestamp.to_rfc3339()),
+            "level" => Some(self.level.as_str().to_string()),
+            "logger" => Some(self.logger.clone()),
+            "message" | "msg" => Some(self.message.clone()),
+            "module" => self.module.clone(),
+            "function" => self.function.clone(),
+            "source" => Some(self.source.clone()),
+            "fingerprint" => Some(self.fingerprint.clone()),
+            "pattern_id" => self.pattern_id.map(|id| id.to_string()),
+            _ => self.get_field_as_string(field_path),
+        }
+    }
+}
@@ -1,10 +1,26 @@
-use jsonweblog::WebServer;
+use clap::Parser;
+use jsonweblog::{
+    cli::{self, Cli, Command, ServeArgs},
+    metric_rules::MetricRule,
+    server::BindAddress,
+    WebServer,
+};
 use tracing::{info, Level};
-use tracing_subscriber;
 use tokio::signal;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Parse command line arguments; invalid input exits with usage help
+    let command = Cli::parse().into_command();
+
+    match command {
+        Command::Serve(args) => serve(args).await,
+        Command::Query(args) => cli::run_query(args).await,
+        Command::Export(args) => cli::run_export(args).await,
+    }
+}
+
+async fn serve(args: ServeArgs) -> anyhow::Result<()> {
     // Initialize tracing
     tracing_subscriber::fmt()
         .with_max_level(Level::INFO)
@@ -14,15 +30,19 @@ async fn main() -> anyhow::Result<()> {
         .with_line_number(true)
         .init();
 
-    // Parse command line arguments
-    let requested_port = std::env::args()
-        .nth(1)
-        .and_then(|s| s.parse::<u16>().ok())
-        .unwrap_or(3000);
+    let metric_rules = args
+        .metric_rules
+        .as_deref()
+        .map(MetricRule::load_from_file)
+        .transpose()?;
 
     // Find an available port starting from the requested port
-    let actual_port = WebServer::find_available_port_for_new(requested_port).await?;
-    
+    let requested_port = args.port();
+    let actual_port = match &args.bind {
+        BindAddress::Ip(ip) if !args.no_auto_port => WebServer::find_available_port(*ip, requested_port).await?,
+        _ => requested_port,
+    };
+
     /*
     if actual_port != requested_port {
         info!("Port {} was not available, using port {} instead", requested_port, actual_port);
@@ -30,15 +50,28 @@ async fn main() -> anyhow::Result<()> {
     */
 
     // Create and start the server
-    let server = WebServer::new(actual_port);
-    
+    let mut server = WebServer::new(actual_port)
+        .with_bind_address(args.bind)
+        .with_max_entries(args.max_entries)
+        .with_open_browser(args.open_browser)
+        .with_collapse_repeats(args.collapse_repeats);
+    if let Some(path) = args.input.file {
+        server = server.with_input_file(path);
+    }
+    if let Some(path) = args.settings {
+        server = server.with_settings_path(path)?;
+    }
+    if let Some(keys) = args.correlation_keys {
+        let keys = keys.into_iter().map(|key| key.trim().to_string()).filter(|key| !key.is_empty()).collect();
+        server = server.with_correlation_keys(keys);
+    }
+    if let Some(rules) = metric_rules {
+        server = server.with_metric_rules(rules)?;
+    }
+
     // Set up graceful shutdown
     tokio::select! {
-        result = server.start() => {
-            if let Err(e) = result {
-                eprintln!("Server error: {}", e);
-            }
-        }
+        result = server.start() => result?,
         _ = signal::ctrl_c() => {
             info!("Received Ctrl+C, shutting down gracefully...");
         }
@@ -0,0 +1,355 @@
+//! Metrics derived from log content.
+//!
+//! A rule selects entries with a query, groups them by label fields and either
+//! counts them or records a numeric field in a histogram, e.g. "errors by `logger`"
+//! or "`duration_ms` by `route`". Rules are read from a JSON file and their series
+//! are exported on `/metrics` next to the viewer's own metrics.
+
+use crate::metrics::{Histogram, MetricsText};
+use crate::log_entry::DynamicFieldValue;
+use crate::{LogEntry, Query};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Histogram buckets used when a rule does not list its own.
+const DEFAULT_BUCKETS: [f64; 12] = [1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0];
+
+/// Label combinations kept per rule; entries with further combinations are not recorded,
+/// so a label on a high-cardinality field cannot grow the output without bound.
+const MAX_SERIES_PER_RULE: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleKind {
+    /// Number of matching entries
+    Counter,
+    /// Distribution of the rule's `field` over matching entries
+    Histogram,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricRule {
+    /// Prometheus metric name, e.g. `app_errors_total`
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: RuleKind,
+    #[serde(default)]
+    pub help: Option<String>,
+    /// Query selecting the entries the rule applies to; all entries when absent
+    #[serde(default)]
+    pub filter: Option<Query>,
+    /// Fields whose values become labels; dots and other characters not allowed in
+    /// label names are replaced with `_`
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Numeric field observed by a histogram
+    #[serde(default)]
+    pub field: Option<String>,
+    /// Upper bounds of the histogram buckets
+    #[serde(default)]
+    pub buckets: Option<Vec<f64>>,
+}
+
+impl MetricRule {
+    /// Reads a JSON array of rules.
+    pub fn load_from_file(path: &Path) -> Result<Vec<MetricRule>> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read metric rules {}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| anyhow!("Invalid metric rules {}: {}", path.display(), e))
+    }
+}
+
+#[derive(Debug)]
+enum Series {
+    Counter(BTreeMap<Vec<String>, u64>),
+    Histogram {
+        field: String,
+        buckets: Vec<f64>,
+        series: BTreeMap<Vec<String>, Histogram>,
+    },
+}
+
+#[derive(Debug)]
+struct CompiledRule {
+    name: String,
+    help: String,
+    filter: Option<Query>,
+    /// Field paths and the label names they are exported as
+    labels: Vec<(String, String)>,
+    series: Mutex<Series>,
+}
+
+impl CompiledRule {
+    fn compile(rule: MetricRule) -> Result<Self> {
+        if !is_metric_name(&rule.name) {
+            return Err(anyhow!("Invalid metric name: {}", rule.name));
+        }
+        // Keep derived series apart from the viewer's own metrics
+        if rule.name.starts_with("jsonweblog_") {
+            return Err(anyhow!("Metric {}: the jsonweblog_ prefix is reserved", rule.name));
+        }
+
+        let mut label_names = HashSet::new();
+        let mut labels = Vec::with_capacity(rule.labels.len());
+        for field in rule.labels {
+            let label = label_name(&field);
+            if label.starts_with("__") || (rule.kind == RuleKind::Histogram && label == "le") {
+                return Err(anyhow!("Metric {}: reserved label name {}", rule.name, label));
+            }
+            if !label_names.insert(label.clone()) {
+                return Err(anyhow!("Metric {}: duplicate label {}", rule.name, label));
+            }
+            labels.push((field, label));
+        }
+
+        let series = match rule.kind {
+            RuleKind::Counter => Series::Counter(BTreeMap::new()),
+            RuleKind::Histogram => {
+                let field = rule
+                    .field
+                    .ok_or_else(|| anyhow!("Metric {}: histograms need a field", rule.name))?;
+                let buckets = rule.buckets.unwrap_or_else(|| DEFAULT_BUCKETS.to_vec());
+                if buckets.is_empty() || buckets.windows(2).any(|pair| pair[0] >= pair[1]) {
+                    return Err(anyhow!("Metric {}: buckets must be increasing", rule.name));
+                }
+                Series::Histogram {
+                    field,
+                    buckets,
+                    series: BTreeMap::new(),
+                }
+            }
+        };
+
+        let help = rule.help.unwrap_or_else(|| {
+            let subject = match &series {
+                Series::Counter(_) => "Log entries".to_string(),
+                Series::Histogram { field, .. } => format!("{} of log entries", field),
+            };
+            match &rule.filter {
+                Some(filter) => format!("{} matching {}", subject, filter.as_str()),
+                None => subject,
+            }
+        });
+
+        Ok(Self {
+            name: rule.name,
+            help,
+            filter: rule.filter,
+            labels,
+            series: Mutex::new(series),
+        })
+    }
+
+    fn record(&self, entries: &[LogEntry]) {
+        let mut series = self.series.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        for entry in entries {
+            if self.filter.as_ref().is_some_and(|filter| !filter.matches(entry)) {
+                continue;
+            }
+            // Entries missing a label field get an empty label value
+            let key: Vec<String> = self
+                .labels
+                .iter()
+                .map(|(field, _)| entry.get_any_field_as_string(field).unwrap_or_default())
+                .collect();
+
+            match &mut *series {
+                Series::Counter(counts) => {
+                    if let Some(count) = counts.get_mut(&key) {
+                        *count += 1;
+                    } else if counts.len() < MAX_SERIES_PER_RULE {
+                        counts.insert(key, 1);
+                    }
+                }
+                Series::Histogram { field, buckets, series } => {
+                    let Some(value) = numeric_value(entry, field) else {
+                        continue;
+                    };
+                    if let Some(histogram) = series.get_mut(&key) {
+                        histogram.observe(value);
+                    } else if series.len() < MAX_SERIES_PER_RULE {
+                        let mut histogram = Histogram::new(buckets);
+                        histogram.observe(value);
+                        series.insert(key, histogram);
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_to(&self, out: &mut MetricsText) {
+        let series = self.series.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match &*series {
+            Series::Counter(counts) => {
+                out.family(&self.name, &self.help, "counter");
+                for (key, count) in counts {
+                    out.sample(&self.name, &self.label_pairs(key), *count as f64);
+                }
+            }
+            Series::Histogram { series, .. } => {
+                out.family(&self.name, &self.help, "histogram");
+                for (key, histogram) in series {
+                    histogram.write_to(out, &self.name, &self.label_pairs(key));
+                }
+            }
+        }
+    }
+
+    fn label_pairs<'a>(&'a self, key: &'a [String]) -> Vec<(&'a str, &'a str)> {
+        self.labels
+            .iter()
+            .zip(key)
+            .map(|((_, label), value)| (label.as_str(), value.as_str()))
+            .collect()
+    }
+}
+
+/// Metrics computed by every configured rule.
+#[derive(Debug, Default)]
+pub struct DerivedMetrics {
+    rules: Vec<CompiledRule>,
+}
+
+impl DerivedMetrics {
+    pub fn new(rules: Vec<MetricRule>) -> Result<Self> {
+        let mut names = HashSet::new();
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                if !names.insert(rule.name.clone()) {
+                    return Err(anyhow!("Duplicate metric name: {}", rule.name));
+                }
+                CompiledRule::compile(rule)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn record(&self, entries: &[LogEntry]) {
+        for rule in &self.rules {
+            rule.record(entries);
+        }
+    }
+
+    pub fn write_to(&self, out: &mut MetricsText) {
+        for rule in &self.rules {
+            rule.write_to(out);
+        }
+    }
+}
+
+/// Value of a numeric field; numeric strings such as `"12.5"` count too.
+fn numeric_value(entry: &LogEntry, field: &str) -> Option<f64> {
+    match entry.get_field_value(field)? {
+        DynamicFieldValue::Number(n) => Some(*n),
+        DynamicFieldValue::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+    .filter(|value: &f64| value.is_finite())
+}
+
+fn is_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_' || first == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+fn label_name(field: &str) -> String {
+    let name: String = field
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{name}")
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonLogParser;
+
+    fn entries(lines: &[&str]) -> Vec<LogEntry> {
+        let parser = JsonLogParser::new();
+        lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| parser.parse_line(line, index as u64 + 1).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_counts_and_histograms_by_label() {
+        let rules: Vec<MetricRule> = serde_json::from_str(
+            r#"[
+                {"name": "app_errors_total", "type": "counter", "filter": "level>=error", "labels": ["logger"]},
+                {"name": "app_request_duration_ms", "type": "histogram", "field": "duration_ms",
+                 "labels": ["http.route"], "buckets": [10, 100]}
+            ]"#,
+        )
+        .unwrap();
+        let derived = DerivedMetrics::new(rules).unwrap();
+        derived.record(&entries(&[
+            r#"{"level": "error", "logger": "db", "message": "timeout"}"#,
+            r#"{"level": "info", "logger": "db", "message": "ok", "duration_ms": 5, "http": {"route": "/a"}}"#,
+            r#"{"level": "error", "logger": "api", "message": "failed", "duration_ms": "250", "http": {"route": "/a"}}"#,
+            r#"{"level": "info", "logger": "api", "message": "no duration"}"#,
+        ]));
+
+        let mut out = MetricsText::new();
+        derived.write_to(&mut out);
+        let text = out.finish();
+        assert!(text.contains("# HELP app_errors_total Log entries matching level>=error\n"));
+        assert!(text.contains("app_errors_total{logger=\"api\"} 1\n"));
+        assert!(text.contains("app_errors_total{logger=\"db\"} 1\n"));
+        assert!(text.contains("# TYPE app_request_duration_ms histogram\n"));
+        assert!(text.contains("app_request_duration_ms_bucket{http_route=\"/a\",le=\"10\"} 1\n"));
+        assert!(text.contains("app_request_duration_ms_bucket{http_route=\"/a\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("app_request_duration_ms_sum{http_route=\"/a\"} 255\n"));
+        assert!(!text.contains("http_route=\"\""));
+    }
+
+    #[test]
+    fn test_escapes_filter_in_help() {
+        let rule = MetricRule {
+            name: "app_windows_errors_total".to_string(),
+            kind: RuleKind::Counter,
+            help: None,
+            filter: Some(Query::parse("level>=error\nAND path~\"C:\\\\temp\"").unwrap()),
+            labels: Vec::new(),
+            field: None,
+            buckets: None,
+        };
+        let derived = DerivedMetrics::new(vec![rule]).unwrap();
+        derived.record(&entries(&[r#"{"level": "error", "path": "C:\\temp\\a.log"}"#]));
+
+        let mut out = MetricsText::new();
+        derived.write_to(&mut out);
+        let text = out.finish();
+        assert!(text.starts_with(
+            "# HELP app_windows_errors_total Log entries matching level>=error\\nAND path~\"C:\\\\\\\\temp\"\n"
+        ));
+        assert!(text.ends_with("app_windows_errors_total 1\n"));
+        assert_eq!(text.lines().count(), 3);
+    }
+
+    #[test]
+    fn test_rejects_invalid_rules() {
+        let rule = |json: &str| serde_json::from_str::<MetricRule>(json).unwrap();
+        assert!(DerivedMetrics::new(vec![rule(r#"{"name": "bad-name", "type": "counter"}"#)]).is_err());
+        assert!(DerivedMetrics::new(vec![rule(r#"{"name": "jsonweblog_errors", "type": "counter"}"#)]).is_err());
+        assert!(DerivedMetrics::new(vec![rule(r#"{"name": "latency", "type": "histogram"}"#)]).is_err());
+        assert!(DerivedMetrics::new(vec![rule(
+            r#"{"name": "latency", "type": "histogram", "field": "ms", "buckets": [5, 1]}"#
+        )])
+        .is_err());
+        assert!(DerivedMetrics::new(vec![rule(r#"{"name": "a", "type": "counter", "labels": ["x.y", "x_y"]}"#)]).is_err());
+    }
+}
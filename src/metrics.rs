@@ -0,0 +1,279 @@
+//! Self-monitoring counters rendered in the Prometheus text exposition format.
+//!
+//! `Metrics` holds the counters updated while ingesting and serving, along with the
+//! metrics derived from log content by configured rules; values that can be read from
+//! the current state (buffer size, connected clients) are added by the `/metrics`
+//! handler when it renders a `MetricsText`.
+
+use crate::metric_rules::{DerivedMetrics, MetricRule};
+use crate::LogEntry;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the query latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 12] = [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+/// Counters keyed by one label value.
+#[derive(Debug, Default)]
+struct LabeledCounter(Mutex<BTreeMap<String, u64>>);
+
+impl LabeledCounter {
+    fn add(&self, label: &str, value: u64) {
+        let mut counts = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match counts.get_mut(label) {
+            Some(count) => *count += value,
+            None => {
+                counts.insert(label.to_string(), value);
+            }
+        }
+    }
+
+    fn snapshot(&self) -> BTreeMap<String, u64> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Histogram {
+    bounds: Vec<f64>,
+    /// Observations per bucket (not cumulative); the last slot counts those above every bound
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    /// `bounds` must be sorted in ascending order.
+    pub(crate) fn new(bounds: &[f64]) -> Self {
+        Self {
+            bounds: bounds.to_vec(),
+            buckets: vec![0; bounds.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    pub(crate) fn observe(&mut self, value: f64) {
+        let bucket = self.bounds.iter().position(|&bound| value <= bound).unwrap_or(self.bounds.len());
+        self.buckets[bucket] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Writes the bucket, sum and count samples of one series.
+    pub(crate) fn write_to(&self, out: &mut MetricsText, name: &str, labels: &[(&str, &str)]) {
+        let bucket_name = format!("{name}_bucket");
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&self.buckets) {
+            cumulative += count;
+            let le = bound.to_string();
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &le));
+            out.sample(&bucket_name, &bucket_labels, cumulative as f64);
+        }
+        let mut bucket_labels = labels.to_vec();
+        bucket_labels.push(("le", "+Inf"));
+        out.sample(&bucket_name, &bucket_labels, self.count as f64);
+        out.sample(&format!("{name}_sum"), labels, self.sum);
+        out.sample(&format!("{name}_count"), labels, self.count as f64);
+    }
+}
+
+#[derive(Debug)]
+pub struct Metrics {
+    lines: LabeledCounter,
+    parse_errors: LabeledCounter,
+    entries: LabeledCounter,
+    evictions: AtomicU64,
+    lagged: AtomicU64,
+    query_latency: Mutex<Histogram>,
+    derived: DerivedMetrics,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            lines: LabeledCounter::default(),
+            parse_errors: LabeledCounter::default(),
+            entries: LabeledCounter::default(),
+            evictions: AtomicU64::new(0),
+            lagged: AtomicU64::new(0),
+            query_latency: Mutex::new(Histogram::new(&LATENCY_BUCKETS)),
+            derived: DerivedMetrics::default(),
+        }
+    }
+
+    /// Also derives metrics from ingested entries with `rules`.
+    pub fn with_rules(rules: Vec<MetricRule>) -> anyhow::Result<Self> {
+        Ok(Self {
+            derived: DerivedMetrics::new(rules)?,
+            ..Self::new()
+        })
+    }
+
+    /// A line was read from `source`, whether or not it parsed.
+    pub fn record_line(&self, source: &str) {
+        self.lines.add(source, 1);
+    }
+
+    pub fn record_parse_error(&self, kind: &str) {
+        self.parse_errors.add(kind, 1);
+    }
+
+    /// Counts ingested entries per level and applies the derived metric rules.
+    pub fn record_entries(&self, entries: &[LogEntry]) {
+        for entry in entries {
+            self.entries.add(entry.level.as_str(), 1);
+        }
+        self.derived.record(entries);
+    }
+
+    pub fn record_evictions(&self, count: u64) {
+        self.evictions.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Live entries a stream skipped because it fell behind the broadcast channel.
+    pub fn record_lagged(&self, skipped: u64) {
+        self.lagged.fetch_add(skipped, Ordering::Relaxed);
+    }
+
+    pub fn observe_query(&self, elapsed: Duration) {
+        let mut histogram = self.query_latency.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        histogram.observe(elapsed.as_secs_f64());
+    }
+
+    pub fn write_to(&self, out: &mut MetricsText) {
+        out.family("jsonweblog_ingested_lines_total", "Lines read per input source.", "counter");
+        for (source, count) in self.lines.snapshot() {
+            out.sample("jsonweblog_ingested_lines_total", &[("source", &source)], count as f64);
+        }
+
+        out.family("jsonweblog_parse_errors_total", "Lines that could not be parsed, by kind.", "counter");
+        for (kind, count) in self.parse_errors.snapshot() {
+            out.sample("jsonweblog_parse_errors_total", &[("kind", &kind)], count as f64);
+        }
+
+        out.family("jsonweblog_entries_total", "Log entries ingested per level.", "counter");
+        for (level, count) in self.entries.snapshot() {
+            out.sample("jsonweblog_entries_total", &[("level", &level)], count as f64);
+        }
+
+        out.counter(
+            "jsonweblog_buffer_evictions_total",
+            "Entries dropped from the front of the buffer to make room.",
+            self.evictions.load(Ordering::Relaxed) as f64,
+        );
+        out.counter(
+            "jsonweblog_broadcast_lagged_entries_total",
+            "Live entries skipped by streaming clients that fell behind and resynchronized.",
+            self.lagged.load(Ordering::Relaxed) as f64,
+        );
+
+        let name = "jsonweblog_query_duration_seconds";
+        out.family(name, "Latency of /api/logs queries.", "histogram");
+        self.query_latency
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .write_to(out, name, &[]);
+
+        self.derived.write_to(out);
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Prometheus text format writer.
+#[derive(Debug, Default)]
+pub struct MetricsText {
+    text: String,
+}
+
+impl MetricsText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a metric family with its help text and type.
+    pub fn family(&mut self, name: &str, help: &str, kind: &str) {
+        let _ = writeln!(self.text, "# HELP {name} {}", escape_help(help));
+        let _ = writeln!(self.text, "# TYPE {name} {kind}");
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.text.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(label, value)| format!("{label}=\"{}\"", escape_label(value)))
+                .collect();
+            let _ = write!(self.text, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.text, " {value}");
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, help, "counter");
+        self.sample(name, &[], value);
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) {
+        self.family(name, help, "gauge");
+        self.sample(name, &[], value);
+    }
+
+    pub fn finish(self) -> String {
+        self.text
+    }
+}
+
+/// Help text may contain anything but must stay on one line.
+fn escape_help(help: &str) -> String {
+    help.replace('\\', r"\\").replace('\n', r"\n")
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', "\\\"").replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonLogParser;
+
+    #[test]
+    fn test_renders_text_format() {
+        let metrics = Metrics::new();
+        metrics.record_line("stdin");
+        metrics.record_line("stdin");
+        metrics.record_parse_error("invalid_json");
+        let entry = JsonLogParser::new()
+            .parse_line(r#"{"level": "warn", "message": "slow"}"#, 1)
+            .unwrap();
+        metrics.record_entries(&[entry]);
+        metrics.observe_query(Duration::from_micros(700));
+        metrics.observe_query(Duration::from_secs(3));
+
+        let mut out = MetricsText::new();
+        metrics.write_to(&mut out);
+        out.gauge("jsonweblog_buffer_entries", "Entries in the buffer.", 1.0);
+        let text = out.finish();
+
+        assert!(text.contains("# TYPE jsonweblog_ingested_lines_total counter\n"));
+        assert!(text.contains("jsonweblog_ingested_lines_total{source=\"stdin\"} 2\n"));
+        assert!(text.contains("jsonweblog_parse_errors_total{kind=\"invalid_json\"} 1\n"));
+        assert!(text.contains("jsonweblog_entries_total{level=\"WARN\"} 1\n"));
+        assert!(text.contains("jsonweblog_query_duration_seconds_bucket{le=\"0.0005\"} 0\n"));
+        assert!(text.contains("jsonweblog_query_duration_seconds_bucket{le=\"0.001\"} 1\n"));
+        assert!(text.contains("jsonweblog_query_duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("jsonweblog_query_duration_seconds_count 2\n"));
+        assert!(text.ends_with("jsonweblog_buffer_entries 1\n"));
+        assert_eq!(escape_label("say \"hi\"\n"), "say \\\"hi\\\"\\n");
+    }
+}
@@ -3,28 +3,80 @@ use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde_json::Value;
 use std::collections::HashMap;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::fmt;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 use tokio_stream::{wrappers::LinesStream, StreamExt};
 
+/// Why a line could not be turned into a log entry.
+#[derive(Debug)]
+pub enum ParseError {
+    Read(std::io::Error),
+    EmptyLine,
+    NotAnObject,
+    InvalidJson(serde_json::Error),
+}
+
+impl ParseError {
+    /// Short label used in metrics.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ParseError::Read(_) => "read_error",
+            ParseError::EmptyLine => "empty_line",
+            ParseError::NotAnObject => "not_object",
+            ParseError::InvalidJson(_) => "invalid_json",
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Read(e) => write!(f, "Failed to read line: {}", e),
+            ParseError::EmptyLine => write!(f, "Empty line"),
+            ParseError::NotAnObject => write!(f, "Expected JSON object but got other JSON type"),
+            ParseError::InvalidJson(e) => write!(f, "Failed to parse JSON: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 pub struct JsonLogParser {
     line_counter: u64,
+    /// Print lines that are not JSON objects to stdout
+    passthrough: bool,
 }
 
 impl JsonLogParser {
     pub fn new() -> Self {
-        Self { line_counter: 0 }
+        Self {
+            line_counter: 0,
+            passthrough: true,
+        }
+    }
+
+    /// Whether lines that are not JSON objects are echoed to stdout (the default).
+    pub fn with_passthrough(mut self, passthrough: bool) -> Self {
+        self.passthrough = passthrough;
+        self
     }
 
     pub async fn parse_stdin(&mut self) -> impl futures::Stream<Item = Result<LogEntry>> + '_ {
-        let stdin = tokio::io::stdin();
-        let reader = BufReader::new(stdin);
-        let lines = LinesStream::new(reader.lines());
+        self.parse_reader(tokio::io::stdin())
+    }
+
+    /// Parses JSONL from any reader, such as a log file.
+    pub fn parse_reader<'a, R: AsyncRead + Unpin + 'a>(
+        &'a mut self,
+        reader: R,
+    ) -> impl futures::Stream<Item = Result<LogEntry>> + 'a {
+        let lines = LinesStream::new(BufReader::new(reader).lines());
 
         lines.map(move |line_result: Result<String, std::io::Error>| {
             self.line_counter += 1;
             match line_result {
                 Ok(line) => self.parse_line(&line, self.line_counter),
-                Err(e) => Err(anyhow!("Failed to read line: {}", e)),
+                Err(e) => Err(ParseError::Read(e).into()),
             }
         })
     }
@@ -32,18 +84,22 @@ impl JsonLogParser {
     pub fn parse_line(&self, line: &str, line_number: u64) -> Result<LogEntry> {
         let line = line.trim();
         if line.is_empty() {
-            return Err(anyhow!("Empty line"));
+            return Err(ParseError::EmptyLine.into());
         }
 
         match serde_json::from_str(line) {
             Ok(Value::Object(obj)) => self.extract_log_entry(obj, line_number),
             Ok(_) => {
-                println!("{}", line); // Not a JSON object
-                Err(anyhow!("Expected JSON object but got other JSON type"))
+                if self.passthrough {
+                    println!("{}", line); // Not a JSON object
+                }
+                Err(ParseError::NotAnObject.into())
             }
             Err(e) => {
-                println!("{}", line); // Not valid JSON
-                Err(anyhow!("Failed to parse JSON: {}", e))
+                if self.passthrough {
+                    println!("{}", line); // Not valid JSON
+                }
+                Err(ParseError::InvalidJson(e).into())
             }
         }
     }
@@ -62,7 +118,7 @@ impl JsonLogParser {
         let logger = self.extract_string_field(&obj, &["logger", "logger_name", "name", "category", "component"])
             .unwrap_or_else(|| "unknown".to_string());
         let message = self.extract_string_field(&obj, &["message", "msg", "text", "description", "content"])
-            .unwrap_or_else(|| "".to_string());
+            .unwrap_or_default();
 
         // Extract optional fields with expanded search
         let module = self.extract_string_field(&obj, &["module", "mod", "component", "file", "filename"]);
@@ -0,0 +1,211 @@
+//! Online message template mining in the style of the Drain algorithm.
+//!
+//! Messages are first normalized (see `grouping::normalize_message`) and split into
+//! tokens. A fixed-depth prefix tree routes each message by its token count and its
+//! first few tokens to a small set of candidate patterns; the most similar candidate
+//! absorbs the message if enough tokens match, turning the differing positions into
+//! `<*>` wildcards. Otherwise a new pattern is created.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+const WILDCARD: &str = "<*>";
+/// Number of leading tokens used to route a message through the tree.
+const PREFIX_DEPTH: usize = 2;
+/// Fraction of non-wildcard tokens that must match for a message to join a pattern.
+const SIMILARITY_THRESHOLD: f64 = 0.5;
+/// Children per tree node before further distinct tokens share a wildcard branch.
+const MAX_CHILDREN: usize = 100;
+/// Patterns kept; once reached, unmatched messages are left untagged.
+const MAX_PATTERNS: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Pattern {
+    pub id: u64,
+    pub template: String,
+    pub count: u64,
+    /// Timestamps of the first and latest entries matching the pattern
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Wall-clock time the pattern was learned, used for "new pattern" counts
+    pub discovered_at: DateTime<Utc>,
+    #[serde(skip)]
+    tokens: Vec<String>,
+}
+
+impl Pattern {
+    /// Returns the share of matching tokens and the number of wildcards.
+    fn similarity(&self, tokens: &[&str]) -> (f64, usize) {
+        let mut matching = 0;
+        let mut wildcards = 0;
+        for (template, token) in self.tokens.iter().zip(tokens) {
+            if template == WILDCARD {
+                wildcards += 1;
+            } else if template == token {
+                matching += 1;
+            }
+        }
+        if tokens.is_empty() {
+            return (1.0, 0);
+        }
+        (matching as f64 / tokens.len() as f64, wildcards)
+    }
+
+    fn absorb(&mut self, tokens: &[&str], timestamp: DateTime<Utc>) {
+        let mut changed = false;
+        for (template, token) in self.tokens.iter_mut().zip(tokens) {
+            if template != token && template != WILDCARD {
+                *template = WILDCARD.to_string();
+                changed = true;
+            }
+        }
+        if changed {
+            self.template = self.tokens.join(" ");
+        }
+        self.count += 1;
+        self.first_seen = self.first_seen.min(timestamp);
+        self.last_seen = self.last_seen.max(timestamp);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<String, Node>,
+    /// Indexes into `PatternMiner::patterns`, only populated at leaves
+    patterns: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternSort {
+    #[default]
+    Count,
+    Recent,
+    New,
+}
+
+#[derive(Debug, Default)]
+pub struct PatternMiner {
+    /// First level of the tree, keyed by token count
+    roots: HashMap<usize, Node>,
+    patterns: Vec<Pattern>,
+}
+
+impl PatternMiner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assigns a normalized message to a pattern, learning a new one if nothing matches.
+    /// Returns the pattern ID, or `None` once the pattern limit has been reached.
+    pub fn add(&mut self, normalized: &str, timestamp: DateTime<Utc>) -> Option<u64> {
+        let tokens: Vec<&str> = normalized.split_whitespace().collect();
+
+        let mut node = self.roots.entry(tokens.len()).or_default();
+        for token in tokens.iter().take(PREFIX_DEPTH) {
+            // Tokens carrying variable data route through the wildcard branch
+            let key = if token.contains(|c: char| c.is_ascii_digit()) || token.contains('<') {
+                WILDCARD
+            } else if node.children.contains_key(*token) || node.children.len() < MAX_CHILDREN {
+                token
+            } else {
+                WILDCARD
+            };
+            node = node.children.entry(key.to_string()).or_default();
+        }
+
+        let best = node
+            .patterns
+            .iter()
+            .map(|&index| (index, self.patterns[index].similarity(&tokens)))
+            .filter(|(_, (similarity, _))| *similarity >= SIMILARITY_THRESHOLD)
+            .max_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+            .map(|(index, _)| index);
+
+        if let Some(index) = best {
+            let pattern = &mut self.patterns[index];
+            pattern.absorb(&tokens, timestamp);
+            return Some(pattern.id);
+        }
+
+        if self.patterns.len() >= MAX_PATTERNS {
+            return None;
+        }
+        let index = self.patterns.len();
+        let id = index as u64 + 1;
+        self.patterns.push(Pattern {
+            id,
+            template: normalized.split_whitespace().collect::<Vec<_>>().join(" "),
+            count: 1,
+            first_seen: timestamp,
+            last_seen: timestamp,
+            discovered_at: Utc::now(),
+            tokens: tokens.iter().map(|token| token.to_string()).collect(),
+        });
+        node.patterns.push(index);
+        Some(id)
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Pattern> {
+        id.checked_sub(1).and_then(|index| self.patterns.get(index as usize))
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Number of patterns learned within `window` of now.
+    pub fn discovered_within(&self, window: Duration) -> usize {
+        let since = Utc::now() - window;
+        // Patterns are appended in discovery order
+        self.patterns.iter().rev().take_while(|pattern| pattern.discovered_at >= since).count()
+    }
+
+    pub fn list(&self, sort: PatternSort, limit: usize) -> Vec<Pattern> {
+        let mut patterns: Vec<&Pattern> = self.patterns.iter().collect();
+        match sort {
+            PatternSort::Count => patterns.sort_by_key(|pattern| Reverse(pattern.count)),
+            PatternSort::Recent => patterns.sort_by_key(|pattern| Reverse(pattern.last_seen)),
+            PatternSort::New => patterns.reverse(),
+        }
+        patterns.into_iter().take(limit).cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.roots.clear();
+        self.patterns.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grouping::normalize_message;
+
+    #[test]
+    fn test_learns_templates() {
+        let mut miner = PatternMiner::new();
+        let now = Utc::now();
+        let mut add = |message: &str| miner.add(&normalize_message(message), now).unwrap();
+
+        let connected = add("Connected to db-primary in 12ms");
+        assert_eq!(add("Connected to db-replica in 40ms"), connected);
+        let resized = add("Worker pool alpha resized to 8");
+        assert_eq!(add("Worker pool beta resized to 16"), resized);
+        assert_ne!(add("Cache miss for key user"), connected);
+
+        assert_eq!(miner.len(), 3);
+        let connected = miner.get(connected).unwrap();
+        assert_eq!(connected.template, "Connected to <*> in <num>ms");
+        assert_eq!(connected.count, 2);
+        assert_eq!(miner.get(resized).unwrap().template, "Worker pool <*> resized to <num>");
+        assert_eq!(miner.discovered_within(Duration::minutes(5)), 3);
+        assert_eq!(miner.list(PatternSort::Count, 1)[0].count, 2);
+    }
+}
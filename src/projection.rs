@@ -0,0 +1,143 @@
+//! Field projection for log entries sent over the HTTP API and WebSocket.
+//!
+//! Without an explicit field list an entry is serialized whole, minus the flattened
+//! `dynamic_fields` which duplicate `raw_fields` and are only included on request.
+//! With a field list only the requested paths are serialized, as a flat object keyed
+//! by the requested names (`{"timestamp": ..., "http.status": 503}`).
+
+use crate::aggregate::parse_field_list;
+use crate::LogEntry;
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default)]
+pub struct Projection {
+    fields: Option<Vec<String>>,
+    include_dynamic: bool,
+}
+
+impl Projection {
+    /// Builds a projection from a comma-separated field list.
+    pub fn new(fields: Option<&str>, include_dynamic: bool) -> Self {
+        let fields = fields.map(parse_field_list).filter(|fields| !fields.is_empty());
+
+        Self {
+            fields,
+            include_dynamic,
+        }
+    }
+
+    pub fn view<'a>(&'a self, entry: &'a LogEntry) -> ProjectedEntry<'a> {
+        ProjectedEntry {
+            projection: self,
+            entry,
+        }
+    }
+
+    pub fn to_json_string(&self, entry: &LogEntry) -> serde_json::Result<String> {
+        serde_json::to_string(&self.view(entry))
+    }
+}
+
+/// Borrowed view of an entry that serializes only the projected fields.
+pub struct ProjectedEntry<'a> {
+    projection: &'a Projection,
+    entry: &'a LogEntry,
+}
+
+#[derive(Serialize)]
+struct EntryWithDynamicFields<'a> {
+    #[serde(flatten)]
+    entry: &'a LogEntry,
+    dynamic_fields: DynamicFieldsView<'a>,
+}
+
+struct DynamicFieldsView<'a>(&'a LogEntry);
+
+impl Serialize for DynamicFieldsView<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.dynamic_fields.len()))?;
+        for (path, value) in &self.0.dynamic_fields {
+            map.serialize_entry(path, &value.to_json())?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for ProjectedEntry<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let entry = self.entry;
+        let Some(fields) = &self.projection.fields else {
+            return if self.projection.include_dynamic {
+                EntryWithDynamicFields {
+                    entry,
+                    dynamic_fields: DynamicFieldsView(entry),
+                }
+                .serialize(serializer)
+            } else {
+                entry.serialize(serializer)
+            };
+        };
+
+        let mut map = serializer.serialize_map(None)?;
+        for field in fields {
+            match field.as_str() {
+                "seq" => map.serialize_entry(field, &entry.seq)?,
+                "line" => map.serialize_entry(field, &entry.line)?,
+                "timestamp" => map.serialize_entry(field, &entry.timestamp)?,
+                "level" => map.serialize_entry(field, &entry.level)?,
+                "logger" => map.serialize_entry(field, &entry.logger)?,
+                "message" | "msg" => map.serialize_entry(field, &entry.message)?,
+                "module" => map.serialize_entry(field, &entry.module)?,
+                "function" => map.serialize_entry(field, &entry.function)?,
+                "source" => map.serialize_entry(field, &entry.source)?,
+                "fingerprint" => map.serialize_entry(field, &entry.fingerprint)?,
+                "pattern_id" => map.serialize_entry(field, &entry.pattern_id)?,
+                "repeat" => map.serialize_entry(field, &entry.repeat)?,
+                "raw_fields" => map.serialize_entry(field, &entry.raw_fields)?,
+                "dynamic_fields" => map.serialize_entry(field, &DynamicFieldsView(entry))?,
+                path => {
+                    if let Some(value) = entry.get_field_value(path) {
+                        map.serialize_entry(field, &value.to_json())?;
+                    }
+                }
+            }
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonLogParser;
+
+    fn entry() -> LogEntry {
+        JsonLogParser::new()
+            .parse_line(r#"{"level": "warn", "message": "slow", "http": {"status": 503}}"#, 7)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_selects_requested_fields() {
+        let projection = Projection::new(Some(" line, level,,http.status ,missing"), false);
+        let json: serde_json::Value = serde_json::from_str(&projection.to_json_string(&entry()).unwrap()).unwrap();
+        assert_eq!(json, serde_json::json!({"line": 7, "level": "Warn", "http.status": 503}));
+
+        // An empty list means no projection
+        let json = Projection::new(Some(" , "), false).to_json_string(&entry()).unwrap();
+        assert!(json.contains("\"raw_fields\""));
+    }
+
+    #[test]
+    fn test_excludes_dynamic_fields_unless_requested() {
+        let json: serde_json::Value =
+            serde_json::from_str(&Projection::new(None, false).to_json_string(&entry()).unwrap()).unwrap();
+        assert!(json.get("dynamic_fields").is_none());
+        assert_eq!(json["raw_fields"]["http"]["status"], 503);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&Projection::new(None, true).to_json_string(&entry()).unwrap()).unwrap();
+        assert_eq!(json["dynamic_fields"]["http.status"], 503);
+    }
+}
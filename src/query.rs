@@ -0,0 +1,634 @@
+//! Expression query language evaluated against `LogEntry` values.
+//!
+//! A query is a boolean combination of terms:
+//!
+//! ```text
+//! level>=warn AND http.status>=500 AND NOT user.id:null AND msg~"timeout"
+//! ```
+//!
+//! - `field:value` / `field=value` test for equality (`:` ignores case for strings)
+//! - `field!=value`, `<`, `<=`, `>`, `>=` compare numbers, levels, timestamps or strings
+//! - `field~value` is a case-insensitive substring match
+//! - `field:*` checks that a field exists, `field:null` that it is missing or null
+//! - a bare word or quoted string searches message, logger, module, function and the
+//!   values of all dynamic fields; a word is only read as a comparison when it starts
+//!   with a field path followed by an operator, so `it's`, `error!`, `12:30` and
+//!   `http://host` are searched for as text
+//! - `AND`, `OR`, `NOT` (uppercase only) and parentheses combine terms; adjacent terms
+//!   are ANDed, so a lowercase `and` is searched for as a word
+//! - values compared with `timestamp` must be RFC 3339 and values compared with `level`
+//!   a known level; anything else is a parse error
+//!
+//! Field names resolve to the built-in entry fields (`level`, `logger`, `message`/`msg`,
+//! `module`, `function`, `line`, `timestamp`, `source`, `fingerprint`, `pattern_id`) first
+//! and to flattened dynamic fields otherwise.
+
+use crate::log_entry::DynamicFieldValue;
+use crate::{LogEntry, LogLevel};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Query {
+    source: String,
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare {
+        field: String,
+        op: CompareOp,
+        value: Literal,
+    },
+    Text(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    EqIgnoreCase,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    String(String),
+    Number(f64),
+    Boolean(bool),
+    Null,
+    Wildcard,
+}
+
+/// A field value resolved from an entry, borrowing where possible.
+enum Resolved<'a> {
+    Level(&'a LogLevel),
+    Time(DateTime<Utc>),
+    Text(&'a str),
+    Number(f64),
+    Boolean(bool),
+    Null,
+    Json(String),
+}
+
+impl Query {
+    pub fn parse(source: &str) -> Result<Self> {
+        let expr = Parser::new(source).parse()?;
+        Ok(Self {
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.expr.matches(entry)
+    }
+}
+
+impl TryFrom<String> for Query {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        Self::parse(&source)
+    }
+}
+
+impl From<Query> for String {
+    fn from(query: Query) -> Self {
+        query.source
+    }
+}
+
+impl Expr {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(entry) && right.matches(entry),
+            Expr::Or(left, right) => left.matches(entry) || right.matches(entry),
+            Expr::Not(inner) => !inner.matches(entry),
+            Expr::Compare { field, op, value } => compare(resolve(entry, field), *op, value),
+            Expr::Text(text) => matches_text(entry, &text.to_lowercase()),
+        }
+    }
+}
+
+fn resolve<'a>(entry: &'a LogEntry, field: &str) -> Option<Resolved<'a>> {
+    match field {
+        "level" => return Some(Resolved::Level(&entry.level)),
+        "timestamp" => return Some(Resolved::Time(entry.timestamp)),
+        "line" => return Some(Resolved::Number(entry.line as f64)),
+        "logger" => return Some(Resolved::Text(&entry.logger)),
+        "message" | "msg" => return Some(Resolved::Text(&entry.message)),
+        "module" => return entry.module.as_deref().map(Resolved::Text),
+        "function" => return entry.function.as_deref().map(Resolved::Text),
+        "source" => return Some(Resolved::Text(&entry.source)),
+        "fingerprint" => return Some(Resolved::Text(&entry.fingerprint)),
+        "pattern_id" => return entry.pattern_id.map(|id| Resolved::Number(id as f64)),
+        _ => {}
+    }
+
+    entry.get_field_value(field).map(|value| match value {
+        DynamicFieldValue::String(s) => Resolved::Text(s),
+        DynamicFieldValue::Number(n) => Resolved::Number(*n),
+        DynamicFieldValue::Boolean(b) => Resolved::Boolean(*b),
+        DynamicFieldValue::Null => Resolved::Null,
+        DynamicFieldValue::Object(obj) => Resolved::Json(obj.to_string()),
+        DynamicFieldValue::Array(arr) => {
+            Resolved::Json(serde_json::to_string(arr).unwrap_or_default())
+        }
+    })
+}
+
+fn compare(resolved: Option<Resolved<'_>>, op: CompareOp, literal: &Literal) -> bool {
+    match literal {
+        Literal::Wildcard => {
+            let exists = !matches!(resolved, None | Some(Resolved::Null));
+            return match op {
+                CompareOp::Ne => !exists,
+                _ => exists,
+            };
+        }
+        Literal::Null => {
+            let is_null = matches!(resolved, None | Some(Resolved::Null));
+            return match op {
+                CompareOp::Ne => !is_null,
+                _ => is_null,
+            };
+        }
+        _ => {}
+    }
+
+    let Some(resolved) = resolved else {
+        return op == CompareOp::Ne;
+    };
+
+    if op == CompareOp::Contains {
+        let needle = literal_text(literal).to_lowercase();
+        return resolved_text(&resolved).to_lowercase().contains(&needle);
+    }
+
+    let ordering = match (&resolved, literal) {
+        (Resolved::Level(level), _) => LogLevel::parse(&literal_text(literal))
+            .map(|other| (*level).cmp(&other)),
+        (Resolved::Time(time), Literal::String(s)) => parse_time_literal(s).map(|other| time.cmp(&other)),
+        (Resolved::Number(n), Literal::Number(other)) => n.partial_cmp(other),
+        (Resolved::Number(n), Literal::String(s)) => s.parse::<f64>().ok().and_then(|other| n.partial_cmp(&other)),
+        (Resolved::Text(s), Literal::Number(other)) => match s.trim().parse::<f64>() {
+            Ok(n) => n.partial_cmp(other),
+            Err(_) => Some(compare_text(s, &literal_text(literal), op)),
+        },
+        (Resolved::Boolean(b), Literal::Boolean(other)) => Some(b.cmp(other)),
+        (Resolved::Null, _) => None,
+        _ => Some(compare_text(&resolved_text(&resolved), &literal_text(literal), op)),
+    };
+
+    match ordering {
+        Some(ordering) => match op {
+            CompareOp::Eq | CompareOp::EqIgnoreCase => ordering == Ordering::Equal,
+            CompareOp::Ne => ordering != Ordering::Equal,
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Le => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Ge => ordering != Ordering::Less,
+            CompareOp::Contains => unreachable!("handled above"),
+        },
+        None => op == CompareOp::Ne,
+    }
+}
+
+fn compare_text(left: &str, right: &str, op: CompareOp) -> Ordering {
+    if op == CompareOp::EqIgnoreCase {
+        left.to_lowercase().cmp(&right.to_lowercase())
+    } else {
+        left.cmp(right)
+    }
+}
+
+fn resolved_text(resolved: &Resolved<'_>) -> String {
+    match resolved {
+        Resolved::Level(level) => level.as_str().to_string(),
+        Resolved::Time(time) => time.to_rfc3339(),
+        Resolved::Text(s) => s.to_string(),
+        Resolved::Number(n) => n.to_string(),
+        Resolved::Boolean(b) => b.to_string(),
+        Resolved::Null => "null".to_string(),
+        Resolved::Json(s) => s.clone(),
+    }
+}
+
+fn literal_text(literal: &Literal) -> String {
+    match literal {
+        Literal::String(s) => s.clone(),
+        Literal::Number(n) => n.to_string(),
+        Literal::Boolean(b) => b.to_string(),
+        Literal::Null => "null".to_string(),
+        Literal::Wildcard => "*".to_string(),
+    }
+}
+
+fn parse_time_literal(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Rejects literals that can never compare against a typed built-in field, so that
+/// `timestamp>2025-07-31` is reported instead of silently matching nothing.
+fn check_typed_literal(field: &str, op: CompareOp, literal: &Literal) -> Result<()> {
+    if op == CompareOp::Contains || matches!(literal, Literal::Wildcard | Literal::Null) {
+        return Ok(());
+    }
+    match field {
+        "timestamp" => match literal {
+            Literal::String(s) if parse_time_literal(s).is_some() => Ok(()),
+            _ => Err(anyhow!(
+                "Invalid timestamp '{}', expected RFC 3339 such as 2025-07-31T12:00:00Z",
+                literal_text(literal)
+            )),
+        },
+        "level" if LogLevel::parse(&literal_text(literal)).is_none() => {
+            Err(anyhow!("Unknown level '{}'", literal_text(literal)))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn matches_text(entry: &LogEntry, needle_lower: &str) -> bool {
+    entry.message.to_lowercase().contains(needle_lower)
+        || entry.logger.to_lowercase().contains(needle_lower)
+        || entry
+            .module
+            .as_ref()
+            .map(|m| m.to_lowercase().contains(needle_lower))
+            .unwrap_or(false)
+        || entry
+            .function
+            .as_ref()
+            .map(|f| f.to_lowercase().contains(needle_lower))
+            .unwrap_or(false)
+        || entry
+            .scalar_field_strings()
+            .any(|value| value.to_lowercase().contains(needle_lower))
+}
+
+/// Whether `s` is a dotted field path such as `http.status` or `@timestamp`.
+fn is_field_path(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_alphabetic() || c == '_' || c == '@')
+        && s.split('.').all(|segment| !segment.is_empty())
+}
+
+/// Recursive-descent parser working directly on the characters of the query, so that
+/// values such as `2025-07-31T12:00:00Z` can contain operator characters.
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn parse(mut self) -> Result<Expr> {
+        self.skip_whitespace();
+        if self.at_end() {
+            return Err(anyhow!("Query is empty"));
+        }
+
+        let expr = self.parse_or()?;
+        self.skip_whitespace();
+        if !self.at_end() {
+            return Err(anyhow!("Unexpected '{}' at position {}", self.rest(), self.pos));
+        }
+        Ok(expr)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut left = self.parse_not()?;
+        loop {
+            self.skip_whitespace();
+            if self.at_end() || self.peek() == Some(')') || self.peek_keyword("OR") {
+                break;
+            }
+            // Explicit AND is optional: adjacent terms are combined with AND
+            self.eat_keyword("AND");
+            let right = self.parse_not()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr> {
+        if self.eat_keyword("NOT") {
+            let inner = self.parse_not()?;
+            return Ok(Expr::Not(Box::new(inner)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err(anyhow!("Unexpected end of query at position {}", self.pos)),
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.parse_or()?;
+                self.skip_whitespace();
+                if self.peek() != Some(')') {
+                    return Err(anyhow!("Expected ')' at position {}", self.pos));
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Some(')') => Err(anyhow!("Unexpected ')' at position {}", self.pos)),
+            Some('"') | Some('\'') => Ok(Expr::Text(self.parse_quoted()?)),
+            Some(_) => self.parse_term(),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Expr> {
+        let start = self.pos;
+        let field = self.take_while(|c| c.is_alphanumeric() || "_.-@".contains(c));
+        if is_field_path(field) {
+            self.skip_whitespace();
+            match self.parse_operator() {
+                // `scheme://...` is a URL, not a comparison
+                Some(CompareOp::EqIgnoreCase) if self.rest().starts_with("//") => {}
+                Some(op) => {
+                    self.skip_whitespace();
+                    let value_start = self.pos;
+                    let value = self.parse_value()?;
+                    check_typed_literal(field, op, &value)
+                        .map_err(|e| anyhow!("{} at position {}", e, value_start))?;
+                    return Ok(Expr::Compare {
+                        field: field.to_string(),
+                        op,
+                        value,
+                    });
+                }
+                None => {}
+            }
+        }
+
+        // Anything else up to whitespace or a parenthesis is a search term
+        self.pos = start;
+        let word = self.take_while(|c| !c.is_whitespace() && c != '(' && c != ')');
+        if word.is_empty() {
+            return Err(anyhow!("Expected a field or search term at position {}", start));
+        }
+        Ok(Expr::Text(word.to_string()))
+    }
+
+    fn parse_operator(&mut self) -> Option<CompareOp> {
+        let operators = [
+            (">=", CompareOp::Ge),
+            ("<=", CompareOp::Le),
+            ("!=", CompareOp::Ne),
+            (">", CompareOp::Gt),
+            ("<", CompareOp::Lt),
+            ("=", CompareOp::Eq),
+            (":", CompareOp::EqIgnoreCase),
+            ("~", CompareOp::Contains),
+        ];
+
+        for (token, op) in operators {
+            if self.rest().starts_with(token) {
+                self.pos += token.len();
+                return Some(op);
+            }
+        }
+        None
+    }
+
+    fn parse_value(&mut self) -> Result<Literal> {
+        match self.peek() {
+            Some('"') | Some('\'') => Ok(Literal::String(self.parse_quoted()?)),
+            Some(_) => {
+                let start = self.pos;
+                let word = self.take_while(|c| !c.is_whitespace() && c != '(' && c != ')');
+                if word.is_empty() {
+                    return Err(anyhow!("Expected a value at position {}", start));
+                }
+                Ok(match word {
+                    "*" => Literal::Wildcard,
+                    "null" => Literal::Null,
+                    "true" => Literal::Boolean(true),
+                    "false" => Literal::Boolean(false),
+                    _ => match word.parse::<f64>() {
+                        Ok(n) if n.is_finite() => Literal::Number(n),
+                        _ => Literal::String(word.to_string()),
+                    },
+                })
+            }
+            None => Err(anyhow!("Expected a value at position {}", self.pos)),
+        }
+    }
+
+    fn parse_quoted(&mut self) -> Result<String> {
+        let start = self.pos;
+        let quote = self.peek().unwrap_or('"');
+        self.pos += quote.len_utf8();
+
+        let mut value = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some((_, escaped)) = chars.next() {
+                        value.push(escaped);
+                    }
+                }
+                c if c == quote => {
+                    self.pos += offset + c.len_utf8();
+                    return Ok(value);
+                }
+                c => value.push(c),
+            }
+        }
+
+        Err(anyhow!("Unterminated string starting at position {}", start))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        if self.peek_keyword(keyword) {
+            self.pos += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        let rest = self.rest();
+        rest.len() >= keyword.len()
+            && rest.is_char_boundary(keyword.len())
+            && &rest[..keyword.len()] == keyword
+            && rest[keyword.len()..]
+                .chars()
+                .next()
+                .map(|c| c.is_whitespace() || c == '(')
+                .unwrap_or(false)
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        let len = self
+            .rest()
+            .char_indices()
+            .find(|(_, c)| !predicate(*c))
+            .map(|(offset, _)| offset)
+            .unwrap_or(self.rest().len());
+        self.pos += len;
+        &self.input[start..self.pos]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn entry(level: LogLevel, message: &str, fields: serde_json::Value) -> LogEntry {
+        let raw_fields: HashMap<String, serde_json::Value> =
+            serde_json::from_value(fields).unwrap();
+        LogEntry::new(1, Utc::now(), level, "api".to_string(), message.to_string())
+            .with_raw_fields(raw_fields)
+    }
+
+    #[test]
+    fn test_combined_query() {
+        let query = Query::parse(
+            r#"level>=warn AND http.status>=500 AND NOT user.id:null AND msg~"timeout""#,
+        )
+        .unwrap();
+
+        let matching = entry(
+            LogLevel::Error,
+            "upstream Timeout",
+            serde_json::json!({"http": {"status": 503}, "user": {"id": "u1"}}),
+        );
+        let anonymous = entry(
+            LogLevel::Error,
+            "upstream timeout",
+            serde_json::json!({"http": {"status": 503}, "user": {"id": null}}),
+        );
+        let info = entry(
+            LogLevel::Info,
+            "upstream timeout",
+            serde_json::json!({"http": {"status": 503}, "user": {"id": "u1"}}),
+        );
+
+        assert!(query.matches(&matching));
+        assert!(!query.matches(&anonymous));
+        assert!(!query.matches(&info));
+    }
+
+    #[test]
+    fn test_or_parentheses_and_existence() {
+        let query = Query::parse("(retry:true OR attempts>2) region:*").unwrap();
+
+        let retried = entry(LogLevel::Info, "x", serde_json::json!({"retry": true, "region": "eu"}));
+        let attempts = entry(LogLevel::Info, "x", serde_json::json!({"attempts": 3, "region": "eu"}));
+        let no_region = entry(LogLevel::Info, "x", serde_json::json!({"retry": true}));
+
+        assert!(query.matches(&retried));
+        assert!(query.matches(&attempts));
+        assert!(!query.matches(&no_region));
+    }
+
+    #[test]
+    fn test_bare_terms_search_text() {
+        let query = Query::parse("\"connection reset\" api").unwrap();
+        let hit = entry(LogLevel::Warn, "Connection reset by peer", serde_json::json!({}));
+        let miss = entry(LogLevel::Warn, "connection closed", serde_json::json!({}));
+
+        assert!(query.matches(&hit));
+        assert!(!query.matches(&miss));
+    }
+
+    #[test]
+    fn test_free_text_with_punctuation() {
+        let text = |query: &str| match Query::parse(query).unwrap().expr() {
+            Expr::Text(text) => text.clone(),
+            other => panic!("{} parsed as {:?}", query, other),
+        };
+        assert_eq!(text("it's"), "it's");
+        assert_eq!(text("error!"), "error!");
+        assert_eq!(text("12:30"), "12:30");
+        assert_eq!(text("http://host/path"), "http://host/path");
+        assert_eq!(text("done."), "done.");
+
+        let query = Query::parse("can't connect").unwrap();
+        assert!(query.matches(&entry(LogLevel::Error, "Can't connect to db", serde_json::json!({}))));
+        assert!(!query.matches(&entry(LogLevel::Error, "can connect", serde_json::json!({}))));
+        let query = Query::parse("(retry OR error!) http://api:8080").unwrap();
+        assert!(query.matches(&entry(LogLevel::Error, "error! calling http://api:8080", serde_json::json!({}))));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Query::parse("").is_err());
+        assert!(Query::parse("(level:error").is_err());
+        assert!(Query::parse("msg~\"open").is_err());
+        assert!(Query::parse("status>=").is_err());
+        assert!(Query::parse("timestamp>2025-07-31").is_err());
+        assert!(Query::parse("timestamp<=2025").is_err());
+        assert!(Query::parse("level>=loud").is_err());
+        assert!(Query::parse("timestamp>2025-07-31T00:00:00Z level:*").is_ok());
+    }
+
+    #[test]
+    fn test_keywords_are_uppercase() {
+        let query = Query::parse("salt and pepper").unwrap();
+        let hit = entry(LogLevel::Info, "salt and pepper", serde_json::json!({}));
+        let miss = entry(LogLevel::Info, "salt, pepper", serde_json::json!({}));
+
+        assert!(query.matches(&hit));
+        assert!(!query.matches(&miss));
+        assert!(Query::parse("not").unwrap().matches(&entry(LogLevel::Info, "not found", serde_json::json!({}))));
+    }
+}
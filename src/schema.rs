@@ -1,10 +1,131 @@
+use crate::log_entry::{DynamicFieldValue, LogEntry};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::path::Path;
 
+/// Distinct values tracked per field before cardinality is reported as a lower bound.
+const MAX_TRACKED_DISTINCT: usize = 1000;
+/// Field paths cataloged; logs with ever-changing keys stop adding paths beyond this.
+const MAX_CATALOG_PATHS: usize = 1000;
+/// Example values kept per field.
+const MAX_EXAMPLES: usize = 5;
+/// Longer string examples are truncated so the catalog stays small.
+const MAX_EXAMPLE_CHARS: usize = 120;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Schema {
-    pub fields: Vec<String>, // Simple list of field names from first log entry
+    pub fields: Vec<String>, // Top-level field names in order of first appearance, "#" first
     pub initialized: bool,
+    /// Number of observed entries still in the buffer
+    #[serde(default)]
+    pub total_entries: u64,
+    /// Catalog of the flattened field paths seen (`http.status`, `tags`, ...)
+    #[serde(default)]
+    pub catalog: BTreeMap<String, FieldInfo>,
+    /// More than `MAX_CATALOG_PATHS` paths were seen; later ones are not cataloged
+    #[serde(default)]
+    pub catalog_capped: bool,
+    #[serde(skip)]
+    field_names: HashSet<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    String,
+    Number,
+    Boolean,
+    Object,
+    Array,
+    Null,
+}
+
+impl FieldType {
+    fn of(value: &DynamicFieldValue) -> Self {
+        match value {
+            DynamicFieldValue::String(_) => FieldType::String,
+            DynamicFieldValue::Number(_) => FieldType::Number,
+            DynamicFieldValue::Boolean(_) => FieldType::Boolean,
+            DynamicFieldValue::Object(_) => FieldType::Object,
+            DynamicFieldValue::Array(_) => FieldType::Array,
+            DynamicFieldValue::Null => FieldType::Null,
+        }
+    }
+}
+
+/// A change in the shape of incoming logs, detected after the first entry was observed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SchemaChange {
+    /// A field path that no earlier entry contained
+    FieldAdded { path: String, field_type: FieldType },
+    /// A known field path carrying a type it has not had before
+    TypeChanged {
+        path: String,
+        previous: BTreeSet<FieldType>,
+        field_type: FieldType,
+    },
+}
+
+impl SchemaChange {
+    pub fn path(&self) -> &str {
+        match self {
+            SchemaChange::FieldAdded { path, .. } | SchemaChange::TypeChanged { path, .. } => path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FieldInfo {
+    /// Every JSON type observed at this path
+    pub types: BTreeSet<FieldType>,
+    /// Number of buffered entries containing this path
+    pub count: u64,
+    /// Percentage of buffered entries containing this path
+    pub coverage: f64,
+    /// A few distinct scalar values seen at this path
+    pub examples: Vec<serde_json::Value>,
+    /// Distinct scalar values seen; a lower bound when `cardinality_capped` is set
+    pub cardinality: usize,
+    pub cardinality_capped: bool,
+    #[serde(skip)]
+    distinct: HashSet<String>,
+}
+
+impl FieldInfo {
+    fn observe(&mut self, value: &DynamicFieldValue) {
+        self.types.insert(FieldType::of(value));
+        self.count += 1;
+
+        // Containers are described by their flattened children instead
+        let key = match value {
+            DynamicFieldValue::String(s) => s.clone(),
+            DynamicFieldValue::Number(n) => n.to_string(),
+            DynamicFieldValue::Boolean(b) => b.to_string(),
+            DynamicFieldValue::Null => "null".to_string(),
+            DynamicFieldValue::Object(_) | DynamicFieldValue::Array(_) => return,
+        };
+        if self.distinct.contains(&key) {
+            return;
+        }
+        if self.distinct.len() >= MAX_TRACKED_DISTINCT {
+            self.cardinality_capped = true;
+            return;
+        }
+
+        if self.examples.len() < MAX_EXAMPLES {
+            let example = match value {
+                DynamicFieldValue::String(s) if s.chars().count() > MAX_EXAMPLE_CHARS => {
+                    let truncated: String = s.chars().take(MAX_EXAMPLE_CHARS).collect();
+                    serde_json::Value::String(format!("{}…", truncated))
+                }
+                _ => value.to_json(),
+            };
+            self.examples.push(example);
+        }
+        self.distinct.insert(key);
+        self.cardinality = self.distinct.len();
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,18 +148,93 @@ impl Schema {
         Self {
             fields: Vec::new(),
             initialized: false,
+            total_entries: 0,
+            catalog: BTreeMap::new(),
+            catalog_capped: false,
+            field_names: HashSet::new(),
         }
     }
 
-    pub fn initialize_from_first_entry(&mut self, raw_fields: &std::collections::HashMap<String, serde_json::Value>) {
+    /// Records the fields of every entry in the batch and refreshes coverage.
+    /// Returns the fields that appeared or changed type since earlier batches.
+    pub fn observe_batch(&mut self, entries: &[LogEntry]) -> Vec<SchemaChange> {
+        let mut changes = Vec::new();
+        for entry in entries {
+            changes.extend(self.observe(entry));
+        }
+        self.update_coverage();
+        changes
+    }
+
+    /// Records the fields of a single entry. Call `update_coverage` afterwards.
+    pub fn observe(&mut self, entry: &LogEntry) -> Vec<SchemaChange> {
         if !self.initialized {
             // Always start with line number field
-            let mut fields = vec!["#".to_string()];
-            // Add fields from raw_fields
-            fields.extend(raw_fields.keys().cloned());
-            self.fields = fields;
+            self.fields.push("#".to_string());
             self.initialized = true;
         }
+        for key in entry.raw_fields.keys() {
+            if self.field_names.len() < MAX_CATALOG_PATHS && self.field_names.insert(key.clone()) {
+                self.fields.push(key.clone());
+            }
+        }
+
+        // The first entry defines the baseline, so nothing in it counts as drift
+        let detect_drift = self.total_entries > 0;
+        self.total_entries += 1;
+
+        let mut changes = Vec::new();
+        for (path, value) in &entry.dynamic_fields {
+            // Array elements are summarized by the array path itself
+            if path.contains('[') {
+                continue;
+            }
+            if !self.catalog.contains_key(path) && self.catalog.len() >= MAX_CATALOG_PATHS {
+                self.catalog_capped = true;
+                continue;
+            }
+            let field_type = FieldType::of(value);
+            let info = self.catalog.entry(path.clone()).or_default();
+            if detect_drift && !info.types.contains(&field_type) {
+                if info.types.is_empty() {
+                    // Objects are announced through their nested paths
+                    if field_type != FieldType::Object {
+                        changes.push(SchemaChange::FieldAdded { path: path.clone(), field_type });
+                    }
+                } else {
+                    changes.push(SchemaChange::TypeChanged {
+                        path: path.clone(),
+                        previous: info.types.clone(),
+                        field_type,
+                    });
+                }
+            }
+            info.observe(value);
+        }
+        changes
+    }
+
+    /// Stops counting entries that left the buffer, so `count` and `coverage` describe
+    /// the buffered entries. Types, examples and cardinality keep the whole session.
+    pub fn forget_batch(&mut self, entries: &[LogEntry]) {
+        for entry in entries {
+            // A folded entry stands for every repeat that was observed
+            let weight = entry.repeat.as_ref().map_or(1, |repeat| repeat.count);
+            self.total_entries = self.total_entries.saturating_sub(weight);
+            for path in entry.dynamic_fields.keys() {
+                if let Some(info) = self.catalog.get_mut(path) {
+                    info.count = info.count.saturating_sub(weight);
+                }
+            }
+        }
+        self.update_coverage();
+    }
+
+    pub fn update_coverage(&mut self) {
+        let total = self.total_entries.max(1) as f64;
+        for info in self.catalog.values_mut() {
+            info.coverage = (info.count as f64 * 1000.0 / total).round() / 10.0;
+        }
     }
 
     pub fn get_default_columns(&self) -> Vec<ColumnConfig> {
@@ -82,4 +278,94 @@ impl Default for Schema {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogLevel;
+    use chrono::Utc;
+
+    fn entry(json: serde_json::Value) -> LogEntry {
+        let fields = serde_json::from_value(json).unwrap();
+        LogEntry::new(1, Utc::now(), LogLevel::Info, "app".to_string(), "msg".to_string())
+            .with_raw_fields(fields)
+    }
+
+    #[test]
+    fn test_catalog_learns_from_every_entry() {
+        let mut schema = Schema::new();
+        schema.observe_batch(&[entry(serde_json::json!({"level": "info", "http": {"status": 200}}))]);
+        schema.observe_batch(&[
+            entry(serde_json::json!({"level": "warn", "http": {"status": "timeout"}})),
+            entry(serde_json::json!({"level": "info", "user": "alice", "tags": ["a", "b"]})),
+        ]);
+
+        // Field order within one entry follows the map, so only check membership
+        assert_eq!(schema.fields[0], "#");
+        for field in ["level", "http", "user", "tags"] {
+            assert!(schema.fields.iter().any(|f| f == field));
+        }
+        assert_eq!(schema.fields.len(), 5);
+        assert_eq!(schema.total_entries, 3);
+
+        let status = &schema.catalog["http.status"];
+        assert_eq!(status.types, BTreeSet::from([FieldType::Number, FieldType::String]));
+        assert_eq!(status.count, 2);
+        assert_eq!(status.coverage, 66.7);
+
+        let level = &schema.catalog["level"];
+        assert_eq!(level.coverage, 100.0);
+        assert_eq!(level.cardinality, 2);
+        assert_eq!(level.examples, vec![serde_json::json!("info"), serde_json::json!("warn")]);
+
+        assert!(schema.catalog.contains_key("tags"));
+        assert!(!schema.catalog.contains_key("tags[0]"));
+    }
+
+    #[test]
+    fn test_schema_drift() {
+        let mut schema = Schema::new();
+        assert!(schema
+            .observe_batch(&[entry(serde_json::json!({"level": "info", "status": 200}))])
+            .is_empty());
+
+        let mut changes = schema.observe_batch(&[
+            entry(serde_json::json!({"level": "info", "status": "timeout", "http": {"route": "/"}})),
+            entry(serde_json::json!({"level": "info", "status": "ok"})),
+        ]);
+        changes.sort_by(|a, b| a.path().cmp(b.path()));
+        assert_eq!(
+            changes,
+            vec![
+                SchemaChange::FieldAdded { path: "http.route".to_string(), field_type: FieldType::String },
+                SchemaChange::TypeChanged {
+                    path: "status".to_string(),
+                    previous: BTreeSet::from([FieldType::Number]),
+                    field_type: FieldType::String,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coverage_follows_eviction_and_paths_are_capped() {
+        let mut schema = Schema::new();
+        let old = entry(serde_json::json!({"level": "info", "status": 200}));
+        schema.observe_batch(&[old.clone(), entry(serde_json::json!({"level": "info"}))]);
+        assert_eq!(schema.catalog["status"].coverage, 50.0);
+
+        schema.forget_batch(&[old]);
+        assert_eq!(schema.total_entries, 1);
+        assert_eq!(schema.catalog["status"].count, 0);
+        assert_eq!(schema.catalog["level"].coverage, 100.0);
+
+        let wide: serde_json::Map<String, serde_json::Value> = (0..MAX_CATALOG_PATHS + 10)
+            .map(|index| (format!("k{index}"), serde_json::json!(index)))
+            .collect();
+        schema.observe_batch(&[entry(serde_json::Value::Object(wide))]);
+        assert_eq!(schema.catalog.len(), MAX_CATALOG_PATHS);
+        assert!(schema.catalog_capped);
+        assert!(schema.fields.len() <= MAX_CATALOG_PATHS + 1);
+    }
+}
@@ -1,40 +1,183 @@
-use crate::{ui::get_static_file, JsonLogParser, LogEntry, LogFilter, LogLevel, schema::{Schema, TableConfig, ColumnConfig}};
+use crate::{ui::get_static_file, filter::{MatchMode, TextMatcher}, time_range::{parse_duration, parse_time_bound}, projection::{Projection, ProjectedEntry}, aggregate::{aggregate, field_stats, histogram, parse_field_list, AggregateResult, FieldStats, Histogram}, grouping::{fingerprint_template, normalize_message, ErrorGroup, ErrorGroups, GroupSort}, patterns::{Pattern, PatternMiner, PatternSort}, trace::{build_trace, CorrelationConfig, Trace}, clients::{ClientHandle, ClientInfo, ClientKind, ClientRegistry}, metrics::{Metrics, MetricsText}, metric_rules::MetricRule, parser::ParseError, wire::{Batch, Encoding, Frame, FrameEncoder}, websocket::{Message, WebSocket, WebSocketUpgrade}, JsonLogParser, LogEntry, LogFilter, LogLevel, Query as LogQuery, schema::{Schema, SchemaChange, TableConfig, ColumnConfig}};
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
-        Query, State,
+        ConnectInfo, Path, Query, State,
+    },
+    http::{header, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
     },
-    http::StatusCode,
-    response::{IntoResponse, Response},
     routing::{any, get},
     Json, Router,
 };
-use futures::{SinkExt, StreamExt};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
     Arc,
 };
 use tokio::{
-    sync::{broadcast, oneshot, RwLock},
-    time::{interval, Duration},
+    sync::{broadcast, mpsc, oneshot, RwLock},
+    time::{interval, sleep_until, Duration, Instant},
 };
+use tokio_stream::wrappers::ReceiverStream;
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
 use tracing::{error, info, warn};
 
+/// Source name of entries read from standard input.
+pub const STDIN_SOURCE: &str = "stdin";
+
+/// Entries kept in the buffer unless configured otherwise; older ones are evicted first.
+pub const DEFAULT_MAX_ENTRIES: usize = 100_000;
+
+/// Where the server listens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindAddress {
+    Ip(IpAddr),
+    /// Path of a Unix domain socket
+    Unix(PathBuf),
+}
+
+impl Default for BindAddress {
+    /// Loopback only, so logs are not exposed to the network unless asked for.
+    fn default() -> Self {
+        BindAddress::Ip(IpAddr::V4(Ipv4Addr::LOCALHOST))
+    }
+}
+
+impl FromStr for BindAddress {
+    type Err = anyhow::Error;
+
+    /// Accepts IPv4 and IPv6 addresses (`::1` or `[::1]`), `localhost`, and Unix
+    /// socket paths, written as `unix:NAME` or as a path containing `/`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let unbracketed = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')).unwrap_or(s);
+        if let Ok(ip) = unbracketed.parse::<IpAddr>() {
+            return Ok(BindAddress::Ip(ip));
+        }
+        if s.eq_ignore_ascii_case("localhost") {
+            return Ok(BindAddress::default());
+        }
+        match s.strip_prefix("unix:") {
+            Some("") => Err(anyhow::anyhow!("Missing Unix socket path after unix:")),
+            Some(path) => Ok(BindAddress::Unix(PathBuf::from(path))),
+            None if s.contains('/') => Ok(BindAddress::Unix(PathBuf::from(s))),
+            None => Err(anyhow::anyhow!(
+                "Expected an IPv4 or IPv6 address or a Unix socket path, e.g. 127.0.0.1, ::, ./jsonweblog.sock"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for BindAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BindAddress::Ip(ip) => write!(f, "{}", ip),
+            BindAddress::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// URLs the web interface can be reached at when listening on `ip`. An unspecified
+/// address lists loopback first, then the address of every network interface; IPv6
+/// `::` also accepts IPv4 connections (see `bind_tcp`).
+fn reachable_urls(ip: IpAddr, port: u16) -> Vec<String> {
+    let url = |ip: IpAddr| format!("http://{}", SocketAddr::new(ip, port));
+    if !ip.is_unspecified() {
+        return vec![url(ip)];
+    }
+
+    let mut addresses = vec![IpAddr::V4(Ipv4Addr::LOCALHOST)];
+    if ip.is_ipv6() {
+        addresses.push(IpAddr::V6(Ipv6Addr::LOCALHOST));
+    }
+    let interfaces = if_addrs::get_if_addrs().unwrap_or_default();
+    let mut external: Vec<IpAddr> = interfaces
+        .iter()
+        .map(|interface| interface.ip())
+        .filter(|address| match address {
+            IpAddr::V4(address) => !address.is_loopback(),
+            // Link-local addresses need a zone ID that browsers don't accept
+            IpAddr::V6(address) => ip.is_ipv6() && !address.is_loopback() && !address.is_unicast_link_local(),
+        })
+        .collect();
+    // An interface can report the same address more than once, not necessarily in a row
+    external.sort();
+    external.dedup();
+    addresses.extend(external);
+    addresses.into_iter().map(url).collect()
+}
+
+/// Listens on `address`. IPv6 sockets are made dual-stack explicitly, so `::` also
+/// accepts IPv4 connections whatever the system default for `IPV6_V6ONLY` is.
+fn bind_tcp(address: SocketAddr) -> std::io::Result<tokio::net::TcpListener> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::for_address(address), Type::STREAM, Some(Protocol::TCP))?;
+    if address.is_ipv6() {
+        socket.set_only_v6(false)?;
+    }
+    // Like `TcpListener::bind`, allow restarting while old connections are in TIME_WAIT
+    #[cfg(unix)]
+    socket.set_reuse_address(true)?;
+    socket.set_nonblocking(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(1024)?;
+    tokio::net::TcpListener::from_std(socket.into())
+}
+
+/// Where the server reads log lines from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogInput {
+    Stdin,
+    File(PathBuf),
+}
+
+impl LogInput {
+    /// Source name given to the entries read from this input.
+    pub fn source_name(&self) -> String {
+        match self {
+            LogInput::Stdin => STDIN_SOURCE.to_string(),
+            LogInput::File(path) => path.display().to_string(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct AppState {
     pub logs: Arc<RwLock<Vec<LogEntry>>>,
-    pub log_tx: broadcast::Sender<String>,
-    pub connection_count: Arc<AtomicU64>,
+    pub log_tx: broadcast::Sender<Arc<LogEntry>>,
+    pub schema_tx: broadcast::Sender<Arc<Vec<SchemaChange>>>,
+    pub clients: Arc<ClientRegistry>,
+    pub metrics: Arc<Metrics>,
+    pub started_at: DateTime<Utc>,
+    pub next_seq: Arc<AtomicU64>,
     pub schema: Arc<RwLock<Schema>>,
     pub table_config: Arc<RwLock<Option<TableConfig>>>,
+    pub groups: Arc<RwLock<ErrorGroups>>,
+    pub patterns: Arc<RwLock<PatternMiner>>,
+    /// Fold consecutive entries with the same fingerprint into one entry
+    pub collapse_repeats: bool,
+    /// Fields used to correlate entries of one trace or request
+    pub correlation: Arc<CorrelationConfig>,
+    /// Entries kept in the buffer
+    pub max_entries: usize,
+    /// File the table configuration is loaded from and saved to
+    pub settings_path: Arc<PathBuf>,
 }
 
 impl AppState {
     pub fn new() -> Self {
-        let (log_tx, _) = broadcast::channel::<String>(1000);
+        let (log_tx, _) = broadcast::channel::<Arc<LogEntry>>(1000);
+        let (schema_tx, _) = broadcast::channel::<Arc<Vec<SchemaChange>>>(16);
         
         // Try to load existing table configuration
         let settings_path = TableConfig::get_settings_path();
@@ -45,61 +188,135 @@ impl AppState {
         Self {
             logs: Arc::new(RwLock::new(Vec::new())),
             log_tx,
-            connection_count: Arc::new(AtomicU64::new(0)),
+            schema_tx,
+            clients: Arc::new(ClientRegistry::new()),
+            metrics: Arc::new(Metrics::new()),
+            started_at: Utc::now(),
+            next_seq: Arc::new(AtomicU64::new(1)),
             schema: Arc::new(RwLock::new(Schema::new())),
             table_config: Arc::new(RwLock::new(table_config)),
+            groups: Arc::new(RwLock::new(ErrorGroups::new())),
+            patterns: Arc::new(RwLock::new(PatternMiner::new())),
+            collapse_repeats: false,
+            correlation: Arc::new(CorrelationConfig::default()),
+            max_entries: DEFAULT_MAX_ENTRIES,
+            settings_path: Arc::new(settings_path),
         }
     }
 
-    pub async fn add_logs_batch(&self, entries: Vec<LogEntry>) {
+    pub async fn add_logs_batch(&self, mut entries: Vec<LogEntry>) {
         if entries.is_empty() {
             return;
         }
 
-        // Acquire write lock once for the entire batch
+        // Tag entries with their message fingerprint and learned pattern
         {
-            let mut logs = self.logs.write().await;
-            logs.extend(entries.iter().cloned()); // Add all new logs
-
-            // Keep only last 100,000 entries to prevent memory issues
-            if logs.len() > 100_000 {
-                let len = logs.len();
-                logs.drain(0..len - 100_000);
+            let mut patterns = self.patterns.write().await;
+            for entry in entries.iter_mut() {
+                let template = normalize_message(&entry.message);
+                entry.fingerprint = fingerprint_template(&template);
+                entry.pattern_id = patterns.add(&template, entry.timestamp);
             }
         }
 
-        // Initialize schema from the first entry of the batch if not already initialized
-        // This assumes that schema initialization only needs to happen once based on any log entry
-        {
+        // Keep the field catalog learning from every entry, not just the first one
+        let mut schema_changes = {
             let mut schema = self.schema.write().await;
-            if !schema.initialized {
-                if let Some(first_entry) = entries.first() {
-                    schema.initialize_from_first_entry(&first_entry.raw_fields);
-
-                    // Auto-generate table config if none exists
-                    if self.table_config.read().await.is_none() && schema.initialized {
-                        let default_columns = schema.get_default_columns();
-                        let config = TableConfig {
-                            theme: None,
-                            columns: default_columns,
-                            auto_scroll: Some(true), // Default to true for auto-scroll
-                        };
-                        *self.table_config.write().await = Some(config);
+            let changes = schema.observe_batch(&entries);
+
+            // Auto-generate table config if none exists
+            if self.table_config.read().await.is_none() && schema.initialized {
+                let default_columns = schema.get_default_columns();
+                let config = TableConfig {
+                    theme: None,
+                    columns: default_columns,
+                    auto_scroll: Some(true), // Default to true for auto-scroll
+                };
+                *self.table_config.write().await = Some(config);
+            }
+            changes
+        };
+
+        // New fields the user already configured (including hidden columns) need no prompt
+        if let Some(config) = self.table_config.read().await.as_ref() {
+            schema_changes.retain(|change| match change {
+                SchemaChange::FieldAdded { path, .. } => {
+                    !config.columns.iter().any(|column| &column.field_name == path)
+                }
+                SchemaChange::TypeChanged { .. } => true,
+            });
+        }
+
+        self.metrics.record_entries(&entries);
+
+        // Acquire write lock once for the entire batch
+        let (updated, evicted) = {
+            let mut logs = self.logs.write().await;
+            let mut groups = self.groups.write().await;
+            let previous_tail = logs.last().map(|entry| entry.seq);
+            let mut tail_folded = false;
+
+            for mut entry in entries {
+                if self.collapse_repeats {
+                    // Only fold repeats from the same place, so the badge never hides a source
+                    if let Some(last) = logs.last_mut().filter(|last| {
+                        last.fingerprint == entry.fingerprint
+                            && last.level == entry.level
+                            && last.logger == entry.logger
+                            && last.module == entry.module
+                            && last.source == entry.source
+                    }) {
+                        last.fold_repeat(&entry);
+                        entry.seq = last.seq;
+                        tail_folded |= Some(last.seq) == previous_tail;
+                        groups.record(&entry);
+                        continue;
                     }
                 }
+
+                // Assign sequence IDs so the buffer stays ordered by `seq` for cursor lookups
+                entry.seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+                groups.record(&entry);
+                logs.push(entry);
+            }
+
+            // New entries, plus the previous tail again if repeats were folded into it
+            let first_updated = match previous_tail {
+                Some(seq) if tail_folded => seq,
+                Some(seq) => seq + 1,
+                None => 0,
+            };
+            let start = logs.partition_point(|entry| entry.seq < first_updated);
+            let updated: Vec<Arc<LogEntry>> = logs[start..].iter().cloned().map(Arc::new).collect();
+
+            // Keep only the newest entries to prevent memory issues
+            let mut evicted = Vec::new();
+            if logs.len() > self.max_entries {
+                let excess = logs.len() - self.max_entries;
+                evicted = logs.drain(0..excess).collect();
+                self.metrics.record_evictions(excess as u64);
             }
+            (updated, evicted)
+        };
+        if !evicted.is_empty() {
+            self.schema.write().await.forget_batch(&evicted);
         }
 
-        // Broadcast each log entry individually to all connected clients
-        for entry in entries {
-            if let Ok(json_string) = serde_json::to_string(&entry) {
-                if let Err(_e) = self.log_tx.send(json_string) {
-                    // warn!("Failed to broadcast log entry: {}", e);
-                    // If a send fails, it usually means no receivers are listening or the channel is full
-                    // For broadcast, it means no active subscribers, so we can just continue
-                }
+        // Broadcast each log entry individually to all connected clients.
+        // Entries are shared so each connection can apply its own filter before serializing.
+        // A folded entry is sent again with the same `seq`; clients replace their copy.
+        for entry in updated {
+            if let Err(_e) = self.log_tx.send(entry) {
+                // warn!("Failed to broadcast log entry: {}", e);
+                // If a send fails, it usually means no receivers are listening or the channel is full
+                // For broadcast, it means no active subscribers, so we can just continue
             }
         }
+
+        // Announce schema changes after the entries that introduced them
+        if !schema_changes.is_empty() {
+            let _ = self.schema_tx.send(Arc::new(schema_changes));
+        }
     }
 
     pub async fn get_logs(&self, filter: Option<LogFilter>) -> Vec<LogEntry> {
@@ -118,35 +335,232 @@ impl AppState {
     pub async fn clear_logs(&self) {
         let mut logs = self.logs.write().await;
         logs.clear();
+        self.groups.write().await.clear();
+        self.patterns.write().await.clear();
+        *self.schema.write().await = Schema::new();
     }
 }
 
-#[derive(Debug, Deserialize)]
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LogQueryParams {
     level: Option<String>,
     search: Option<String>,
+    search_mode: Option<MatchMode>,
+    search_case_sensitive: Option<bool>,
     logger: Option<String>,
+    logger_mode: Option<MatchMode>,
+    logger_case_sensitive: Option<bool>,
     module: Option<String>,
+    module_mode: Option<MatchMode>,
+    module_case_sensitive: Option<bool>,
+    q: Option<String>,
+    start: Option<String>,
+    end: Option<String>,
+    around: Option<u64>,
+    window: Option<String>,
+    after: Option<u64>,
+    before: Option<u64>,
+    offset: Option<usize>,
+    sort: Option<String>,
+    order: Option<SortOrder>,
     limit: Option<usize>,
+    #[serde(flatten)]
+    projection: ProjectionParams,
+    /// WebSocket only: continue a stream after this sequence ID instead of replaying the buffer
+    resume_after: Option<u64>,
 }
 
-#[derive(Debug, Serialize)]
-pub struct LogResponse {
-    logs: Vec<LogEntry>,
+/// Field selection shared by every endpoint that returns entries.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectionParams {
+    fields: Option<String>,
+    /// Query strings reach flattened structs as text, so `"true"` is accepted too
+    #[serde(default, deserialize_with = "bool_or_text")]
+    include_dynamic: Option<bool>,
+}
+
+impl ProjectionParams {
+    fn projection(&self) -> Projection {
+        Projection::new(self.fields.as_deref(), self.include_dynamic.unwrap_or(false))
+    }
+}
+
+fn bool_or_text<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrText {
+        Bool(bool),
+        Text(String),
+    }
+
+    match Option::<BoolOrText>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(BoolOrText::Bool(value)) => Ok(Some(value)),
+        Some(BoolOrText::Text(text)) => text
+            .parse()
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom(format!("expected true or false, got {}", text))),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl LogQueryParams {
+    /// The parameters that were given, for display.
+    fn describe(&self) -> serde_json::Value {
+        let mut params = serde_json::to_value(self).unwrap_or_default();
+        if let Some(params) = params.as_object_mut() {
+            params.retain(|_, value| !value.is_null());
+        }
+        params
+    }
+
+    fn projection(&self) -> Projection {
+        self.projection.projection()
+    }
+
+    /// Builds the filter described by the query string, rejecting malformed expressions.
+    /// Relative times such as `now-15m` are resolved when the filter is built.
+    async fn to_filter(&self, state: &AppState) -> anyhow::Result<LogFilter> {
+        let mut filter = LogFilter::new();
+
+        if let Some(level) = &self.level {
+            if !level.is_empty() {
+                filter = filter.with_level(LogLevel::from_str(level));
+            }
+        }
+
+        // Search is case-insensitive by default, logger and module are case-sensitive
+        if let Some(search) = &self.search {
+            if !search.is_empty() {
+                let matcher = TextMatcher::new(
+                    search.clone(),
+                    self.search_mode.unwrap_or_default(),
+                    self.search_case_sensitive.unwrap_or(false),
+                ).map_err(|e| anyhow::anyhow!("Invalid search: {}", e))?;
+                filter = filter.with_search_matcher(matcher);
+            }
+        }
+
+        if let Some(logger) = &self.logger {
+            if !logger.is_empty() {
+                let matcher = TextMatcher::new(
+                    logger.clone(),
+                    self.logger_mode.unwrap_or_default(),
+                    self.logger_case_sensitive.unwrap_or(true),
+                ).map_err(|e| anyhow::anyhow!("Invalid logger filter: {}", e))?;
+                filter = filter.with_logger_matcher(matcher);
+            }
+        }
+
+        if let Some(module) = &self.module {
+            if !module.is_empty() {
+                let matcher = TextMatcher::new(
+                    module.clone(),
+                    self.module_mode.unwrap_or_default(),
+                    self.module_case_sensitive.unwrap_or(true),
+                ).map_err(|e| anyhow::anyhow!("Invalid module filter: {}", e))?;
+                filter = filter.with_module_matcher(matcher);
+            }
+        }
+
+        if let Some(q) = &self.q {
+            if !q.trim().is_empty() {
+                let query = LogQuery::parse(q).map_err(|e| anyhow::anyhow!("Invalid query: {}", e))?;
+                filter = filter.with_query(query);
+            }
+        }
+
+        let now = Utc::now();
+        if let Some(start) = self.start.as_deref().filter(|s| !s.is_empty()) {
+            let start = parse_time_bound(start, now).map_err(|e| anyhow::anyhow!("Invalid start: {}", e))?;
+            filter = filter.with_start_time(start);
+        }
+
+        if let Some(end) = self.end.as_deref().filter(|s| !s.is_empty()) {
+            let end = parse_time_bound(end, now).map_err(|e| anyhow::anyhow!("Invalid end: {}", e))?;
+            filter = filter.with_end_time(end);
+        }
+
+        // "around entry N" centers the range on the timestamp of the entry with sequence
+        // ID N (default ±30s); line numbers would miss entries folded by collapse-repeats
+        if let Some(seq) = self.around {
+            let window = match self.window.as_deref().filter(|s| !s.is_empty()) {
+                Some(window) => parse_duration(window).map_err(|e| anyhow::anyhow!("Invalid window: {}", e))?,
+                None => chrono::Duration::seconds(30),
+            };
+            let center = {
+                let logs = state.logs.read().await;
+                logs.binary_search_by_key(&seq, |entry| entry.seq)
+                    .ok()
+                    .map(|index| logs[index].timestamp)
+                    .ok_or_else(|| anyhow::anyhow!("Entry {} is not in the buffer", seq))?
+            };
+            let (start, end) = center.checked_sub_signed(window)
+                .zip(center.checked_add_signed(window))
+                .ok_or_else(|| anyhow::anyhow!("Invalid window: out of range"))?;
+            filter = filter.with_time_range(start, end);
+        }
+
+        Ok(filter)
+    }
+
+    /// Builds the filter of a live stream. A relative `end` such as `now` would only be
+    /// resolved once at connect time and then silently drop every later entry, so it is
+    /// rejected.
+    async fn to_live_filter(&self, state: &AppState) -> anyhow::Result<LogFilter> {
+        if self.end.as_deref().is_some_and(|end| end.trim().starts_with("now")) {
+            return Err(anyhow::anyhow!("Invalid end: relative times are not supported on live streams"));
+        }
+        self.to_filter(state).await
+    }
+}
+
+#[derive(Serialize)]
+pub struct LogResponse<'a> {
+    logs: Vec<ProjectedEntry<'a>>,
     total_count: usize,
     filtered_count: usize,
+    /// Sequence ID to pass as `after` (ascending pages) or `before` (tail and
+    /// descending pages) to continue paging; absent when no entries remain.
+    next_cursor: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct StatsResponse {
     total_logs: usize,
-    active_connections: u64,
+    active_connections: usize,
     uptime_seconds: u64,
 }
 
+/// Unix socket file that is deleted when the server stops listening on it.
+#[cfg(unix)]
+struct SocketFile(PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
 pub struct WebServer {
     state: AppState,
     port: u16,
+    bind: BindAddress,
+    input: LogInput,
+    open_browser: bool,
 }
 
 impl WebServer {
@@ -154,24 +568,109 @@ impl WebServer {
         Self {
             state: AppState::new(),
             port,
+            bind: BindAddress::default(),
+            input: LogInput::Stdin,
+            open_browser: false,
         }
     }
 
+    /// Sets the address or Unix socket to listen on; the port is unused for sockets.
+    pub fn with_bind_address(mut self, bind: BindAddress) -> Self {
+        self.bind = bind;
+        self
+    }
+
+    /// Reads logs from a file instead of stdin. The server keeps running after the
+    /// whole file is loaded, while closing stdin stops it.
+    pub fn with_input_file(mut self, path: PathBuf) -> Self {
+        self.input = LogInput::File(path);
+        self
+    }
+
+    /// Opens the web interface in the default browser once the server is listening.
+    pub fn with_open_browser(mut self, open_browser: bool) -> Self {
+        self.open_browser = open_browser;
+        self
+    }
+
+    /// Sets how many entries the buffer keeps.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.state.max_entries = max_entries;
+        self
+    }
+
+    /// Loads and saves the table configuration at `path` instead of the working directory.
+    /// A missing file is created on the first save; fails if the file cannot be read or parsed.
+    pub fn with_settings_path(mut self, path: PathBuf) -> anyhow::Result<Self> {
+        let table_config = if path.exists() {
+            let config = TableConfig::load_from_file(&path)
+                .map_err(|e| anyhow::anyhow!("Invalid settings file {}: {}", path.display(), e))?;
+            Some(config)
+        } else {
+            None
+        };
+        self.state.table_config = Arc::new(RwLock::new(table_config));
+        self.state.settings_path = Arc::new(path);
+        Ok(self)
+    }
+
+    /// Enables folding of consecutive repeated entries at ingest.
+    pub fn with_collapse_repeats(mut self, collapse_repeats: bool) -> Self {
+        self.state.collapse_repeats = collapse_repeats;
+        self
+    }
+
+    /// Derives Prometheus metrics from ingested entries; fails if a rule is invalid.
+    pub fn with_metric_rules(mut self, rules: Vec<MetricRule>) -> anyhow::Result<Self> {
+        self.state.metrics = Arc::new(Metrics::with_rules(rules)?);
+        Ok(self)
+    }
+
+    /// Sets the fields that identify a trace or request, e.g. `trace_id`.
+    pub fn with_correlation_keys(mut self, keys: Vec<String>) -> Self {
+        self.state.correlation = Arc::new(CorrelationConfig::with_keys(keys));
+        self
+    }
+
     pub async fn start(&self) -> anyhow::Result<()> {
         let app = self.create_router();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
 
-        let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", self.port)).await?;
-        info!("Web interface available at http://localhost:{}", self.port);
+        // Open the input before listening so a bad path fails at startup
+        let file = match &self.input {
+            LogInput::Stdin => None,
+            LogInput::File(path) => Some(
+                tokio::fs::File::open(path)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?,
+            ),
+        };
 
-        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let ip = match &self.bind {
+            BindAddress::Ip(ip) => *ip,
+            BindAddress::Unix(path) => return self.serve_unix(app, path, file, shutdown_tx, shutdown_rx).await,
+        };
 
-        // Start stdin parser task
-        let parser_state = self.state.clone();
-        tokio::spawn(async move {
-            Self::stdin_parser_task(parser_state, shutdown_tx).await;
-        });
+        let address = SocketAddr::new(ip, self.port);
+        let listener = bind_tcp(address)
+            .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", address, e))?;
+        let urls = reachable_urls(ip, self.port);
+        for url in &urls {
+            info!("Web interface available at {}", url);
+        }
+        if !ip.is_loopback() {
+            warn!("Listening on {}, so logs are reachable from other machines", ip);
+        }
+
+        if self.open_browser {
+            if let Err(e) = open::that_detached(&urls[0]) {
+                warn!("Failed to open a browser: {}", e);
+            }
+        }
 
-        axum::serve(listener, app)
+        self.spawn_parser(file, shutdown_tx);
+
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
             .with_graceful_shutdown(async {
                 shutdown_rx.await.ok();
             })
@@ -180,12 +679,107 @@ impl WebServer {
         Ok(())
     }
 
-    pub async fn find_available_port_for_new(port: u16) -> anyhow::Result<u16> {
+    /// Serves HTTP on a Unix domain socket. Requests carry no `ConnectInfo`, so
+    /// clients are listed without a remote address.
+    #[cfg(unix)]
+    async fn serve_unix(
+        &self,
+        app: Router,
+        path: &std::path::Path,
+        file: Option<tokio::fs::File>,
+        shutdown_tx: oneshot::Sender<()>,
+        mut shutdown_rx: oneshot::Receiver<()>,
+    ) -> anyhow::Result<()> {
+        use hyper_util::{
+            rt::{TokioExecutor, TokioIo},
+            server::conn::auto::Builder,
+            service::TowerToHyperService,
+        };
+        use std::os::unix::fs::FileTypeExt;
+
+        // Replace a socket left behind by an earlier run, but not one still in use
+        let is_socket = std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket());
+        if is_socket && std::os::unix::net::UnixStream::connect(path).is_err() {
+            std::fs::remove_file(path)?;
+        }
+        let listener = tokio::net::UnixListener::bind(path)
+            .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", path.display(), e))?;
+        // Removes the socket file however serving ends, including when this future is
+        // dropped on Ctrl+C
+        let _socket_file = SocketFile(path.to_path_buf());
+        info!(
+            "Web interface available on Unix socket {} (curl --unix-socket {} http://localhost/)",
+            path.display(),
+            path.display()
+        );
+        if self.open_browser {
+            warn!("Browsers cannot open a Unix socket; not opening one");
+        }
+
+        self.spawn_parser(file, shutdown_tx);
+
+        loop {
+            let stream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        warn!("Failed to accept a connection: {}", e);
+                        continue;
+                    }
+                },
+                _ = &mut shutdown_rx => break,
+            };
+            let service = TowerToHyperService::new(app.clone());
+            tokio::spawn(async move {
+                // Errors here are clients going away mid-request
+                let _ = Builder::new(TokioExecutor::new())
+                    .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                    .await;
+            });
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    async fn serve_unix(
+        &self,
+        _app: Router,
+        _path: &std::path::Path,
+        _file: Option<tokio::fs::File>,
+        _shutdown_tx: oneshot::Sender<()>,
+        _shutdown_rx: oneshot::Receiver<()>,
+    ) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("Unix domain sockets are not supported on this platform"))
+    }
+
+    /// Starts reading logs from `file`, or stdin when there is none, in the background;
+    /// `shutdown_tx` fires when stdin closes.
+    fn spawn_parser(&self, file: Option<tokio::fs::File>, shutdown_tx: oneshot::Sender<()>) {
+        let parser_state = self.state.clone();
+        let input = self.input.clone();
+        tokio::spawn(async move {
+            Self::parser_task(parser_state, input.source_name(), file).await;
+            match input {
+                // Closing stdin ends the session along with the process feeding it
+                LogInput::Stdin => {
+                    if shutdown_tx.send(()).is_err() {
+                        error!("Failed to send shutdown signal.");
+                    }
+                }
+                // A loaded file stays browsable; holding the sender keeps the server up
+                LogInput::File(_) => std::future::pending::<()>().await,
+            }
+        });
+    }
+
+    /// Finds the first port from `port` upwards that can be bound on `bind`.
+    pub async fn find_available_port(bind: IpAddr, port: u16) -> anyhow::Result<u16> {
         let mut current_port = port;
         const MAX_PORT_ATTEMPTS: u16 = 100;
 
         for _ in 0..MAX_PORT_ATTEMPTS {
-            match tokio::net::TcpListener::bind(format!("0.0.0.0:{}", current_port)).await {
+            match bind_tcp(SocketAddr::new(bind, current_port)) {
                 Ok(_) => {
                     /*
                     if current_port != port {
@@ -216,12 +810,23 @@ impl WebServer {
             // API routes
             .route("/api/logs", get(get_logs_handler))
             .route("/api/logs/clear", axum::routing::post(clear_logs_handler))
+            .route("/api/logs/:seq/context", get(context_handler))
             .route("/api/stats", get(get_stats_handler))
+            .route("/api/aggregate", get(aggregate_handler))
+            .route("/api/histogram", get(histogram_handler))
+            .route("/api/stats/field", get(field_stats_handler))
+            .route("/api/groups", get(groups_handler))
+            .route("/api/patterns", get(patterns_handler))
+            .route("/api/correlation", get(correlation_handler))
+            .route("/api/trace/:id", get(trace_handler))
             .route("/api/schema", get(get_schema_handler))
             .route("/api/schema/columns", get(get_columns_handler))
             .route("/api/schema/columns", axum::routing::post(set_columns_handler))
-            // WebSocket route
+            // Streaming routes
             .route("/ws", any(websocket_handler))
+            .route("/api/stream", get(stream_handler))
+            .route("/api/clients", get(clients_handler))
+            .route("/metrics", get(metrics_handler))
             // Catch-all for static files
             .fallback(serve_static)
             .layer(
@@ -231,9 +836,12 @@ impl WebServer {
             .with_state(self.state.clone())
     }
 
-    async fn stdin_parser_task(state: AppState, shutdown_tx: tokio::sync::oneshot::Sender<()>) {
+    async fn parser_task(state: AppState, source: String, file: Option<tokio::fs::File>) {
         let mut parser = JsonLogParser::new();
-        let mut stream = parser.parse_stdin().await;
+        let mut stream: std::pin::Pin<Box<dyn futures::Stream<Item = anyhow::Result<LogEntry>> + Send + '_>> = match file {
+            Some(file) => Box::pin(parser.parse_reader(file)),
+            None => Box::pin(parser.parse_stdin().await),
+        };
         
         const BATCH_SIZE: usize = 1000;
         const FLUSH_INTERVAL_MS: u64 = 100;
@@ -248,18 +856,29 @@ impl WebServer {
             tokio::select! {
                 // Prefer receiving a new log entry
                 result = stream.next() => {
+                    // Blank lines are separators, neither entries nor parse errors
+                    if let Some(Err(e)) = &result {
+                        if matches!(e.downcast_ref::<ParseError>(), Some(ParseError::EmptyLine)) {
+                            continue;
+                        }
+                    }
+                    if result.is_some() {
+                        state.metrics.record_line(&source);
+                    }
                     match result {
                         Some(Ok(entry)) => {
-                            log_buffer.push(entry);
+                            log_buffer.push(entry.with_source(source.as_str()));
                             if log_buffer.len() >= BATCH_SIZE {
                                 state.add_logs_batch(std::mem::take(&mut log_buffer)).await;
                             }
                         }
-                        Some(Err(_)) => {
-                            // Ignore parsing errors.
+                        Some(Err(e)) => {
+                            // Skip lines that aren't log entries, counting why
+                            let kind = e.downcast_ref::<ParseError>().map_or("other", ParseError::kind);
+                            state.metrics.record_parse_error(kind);
                         }
                         None => {
-                            // Stdin closed, flush any remaining logs and exit
+                            // Input closed, flush any remaining logs and exit
                             if !log_buffer.is_empty() {
                                 state.add_logs_batch(std::mem::take(&mut log_buffer)).await;
                             }
@@ -275,10 +894,6 @@ impl WebServer {
                 }
             }
         }
-
-        if shutdown_tx.send(()).is_err() {
-            error!("Failed to send shutdown signal.");
-        }
     }
 }
 
@@ -316,59 +931,378 @@ async fn serve_static(uri: axum::http::Uri) -> Response {
 async fn get_logs_handler(
     State(state): State<AppState>,
     Query(params): Query<LogQueryParams>,
-) -> Json<LogResponse> {
-    let mut filter = LogFilter::new();
+) -> Result<Response, (StatusCode, String)> {
+    let started = Instant::now();
+    let response = query_logs(&state, params).await;
+    state.metrics.observe_query(started.elapsed());
+    response
+}
+
+async fn query_logs(state: &AppState, params: LogQueryParams) -> Result<Response, (StatusCode, String)> {
+    let projection = params.projection();
+    let filter = params
+        .to_filter(state)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
     
-    if let Some(level) = params.level {
-        if !level.is_empty() {
-            filter = filter.with_level(LogLevel::from_str(&level));
-        }
+    // Sequence cursors cannot continue a page ordered by another field; page with `offset`
+    if params.sort.as_deref().is_some_and(|s| !s.is_empty() && s != "seq")
+        && (params.after.is_some() || params.before.is_some())
+    {
+        return Err((StatusCode::BAD_REQUEST, "after and before cannot be combined with sort; use offset".to_string()));
     }
     
-    if let Some(search) = params.search {
-        if !search.is_empty() {
-            filter = filter.with_search_text(search);
-        }
+    let all_logs = state.logs.read().await;
+    let total_count = all_logs.len();
+    
+    // Work on references so only the returned page is cloned
+    let matched: Vec<&LogEntry> = all_logs.iter()
+        .filter(|entry| filter.is_empty() || filter.matches(entry))
+        .collect();
+    let filtered_count = matched.len();
+    
+    // Cursors bound the sequence range; `matched` is still in sequence order here
+    let lower = params.after.map(|after| matched.partition_point(|entry| entry.seq <= after)).unwrap_or(0);
+    let upper = params.before.map(|before| matched.partition_point(|entry| entry.seq < before)).unwrap_or(matched.len());
+    let mut window = matched[lower..upper.max(lower)].to_vec();
+    
+    let sort_field = params.sort.as_deref().filter(|s| !s.is_empty() && *s != "seq");
+    let order = params.order.unwrap_or(SortOrder::Asc);
+    match sort_field {
+        Some(field) => window.sort_by(|a, b| compare_entries_by_field(a, b, field, order)),
+        None if order == SortOrder::Desc => window.reverse(),
+        None => {}
     }
     
-    if let Some(logger) = params.logger {
-        if !logger.is_empty() {
-            filter = filter.with_logger(logger);
-        }
+    // Ascending sequence pages without `after`/`offset` are anchored at the newest end,
+    // so a bare `limit` keeps returning the tail and `before` pages backwards
+    let offset = params.offset.unwrap_or(0).min(window.len());
+    let limit = params.limit.unwrap_or(window.len());
+    let tail_anchored = sort_field.is_none() && order == SortOrder::Asc && params.after.is_none() && params.offset.is_none();
+    let (page, has_more) = if tail_anchored {
+        let start = window.len().saturating_sub(limit);
+        (&window[start..], start > 0)
+    } else {
+        let end = offset.saturating_add(limit).min(window.len());
+        (&window[offset..end], end < window.len())
+    };
+    
+    let next_cursor = match (has_more && sort_field.is_none(), tail_anchored) {
+        (false, _) => None,
+        (true, true) => page.first().map(|entry| entry.seq),
+        (true, false) => page.last().map(|entry| entry.seq),
+    };
+    
+    // Serialize straight from the buffer while the read lock is held
+    let logs = page.iter().map(|entry| projection.view(entry)).collect();
+    
+    Ok(Json(LogResponse {
+        logs,
+        total_count,
+        filtered_count,
+        next_cursor,
+    }).into_response())
+}
+
+/// Orders entries by a built-in or dynamic field in `order`; numbers compare numerically,
+/// ties keep sequence order and entries missing the field sort last in either direction.
+fn compare_entries_by_field(a: &LogEntry, b: &LogEntry, field: &str, order: SortOrder) -> std::cmp::Ordering {
+    use crate::log_entry::DynamicFieldValue;
+    use std::cmp::Ordering;
+
+    let ordering = match field {
+        "timestamp" => a.timestamp.cmp(&b.timestamp),
+        "line" => a.line.cmp(&b.line),
+        "level" => a.level.cmp(&b.level),
+        "logger" => a.logger.cmp(&b.logger),
+        "message" | "msg" => a.message.cmp(&b.message),
+        _ => match (a.get_field_value(field), b.get_field_value(field)) {
+            (Some(DynamicFieldValue::Number(x)), Some(DynamicFieldValue::Number(y))) => {
+                x.partial_cmp(y).unwrap_or(Ordering::Equal)
+            }
+            (Some(_), Some(_)) => a.get_field_as_string(field).cmp(&b.get_field_as_string(field)),
+            (Some(_), None) => return Ordering::Less,
+            (None, Some(_)) => return Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        },
+    }
+    .then(a.seq.cmp(&b.seq));
+
+    match order {
+        SortOrder::Asc => ordering,
+        SortOrder::Desc => ordering.reverse(),
     }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AggregateParams {
+    group_by: Option<String>,
+    top: Option<usize>,
+}
+
+async fn aggregate_handler(
+    State(state): State<AppState>,
+    Query(params): Query<LogQueryParams>,
+    Query(aggregate_params): Query<AggregateParams>,
+) -> Result<Json<AggregateResult>, (StatusCode, String)> {
+    let filter = params
+        .to_filter(&state)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
     
-    if let Some(module) = params.module {
-        if !module.is_empty() {
-            filter = filter.with_module(module);
-        }
+    let group_by = aggregate_params.group_by
+        .as_deref()
+        .map(parse_field_list)
+        .unwrap_or_default();
+    if group_by.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "group_by must name at least one field".to_string()));
     }
     
-    let all_logs = state.logs.read().await;
-    let total_count = all_logs.len();
+    let logs = state.logs.read().await;
+    let result = aggregate(
+        logs.iter().filter(|entry| filter.matches(entry)),
+        &group_by,
+        aggregate_params.top.unwrap_or(10),
+    );
     
-    let filtered_logs = if filter.is_empty() {
-        all_logs.clone()
-    } else {
-        all_logs.iter()
-            .filter(|entry| filter.matches(entry))
+    Ok(Json(result))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistogramParams {
+    interval: Option<String>,
+}
+
+async fn histogram_handler(
+    State(state): State<AppState>,
+    Query(params): Query<LogQueryParams>,
+    Query(histogram_params): Query<HistogramParams>,
+) -> Result<Json<Histogram>, (StatusCode, String)> {
+    let filter = params
+        .to_filter(&state)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    
+    let interval = match histogram_params.interval.as_deref() {
+        None | Some("") | Some("auto") => None,
+        Some(interval) => Some(
+            parse_duration(interval)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid interval: {}", e)))?,
+        ),
+    };
+    
+    let logs = state.logs.read().await;
+    let result = histogram(
+        logs.iter().filter(|entry| filter.matches(entry)),
+        interval,
+        filter.start_time,
+        filter.end_time,
+    )
+    .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    
+    Ok(Json(result))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GroupsParams {
+    min_level: Option<String>,
+    sort: Option<GroupSort>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupsResponse {
+    total_groups: usize,
+    groups: Vec<ErrorGroup>,
+}
+
+async fn groups_handler(
+    State(state): State<AppState>,
+    Query(params): Query<GroupsParams>,
+) -> Result<Json<GroupsResponse>, (StatusCode, String)> {
+    let min_level = match params.min_level.as_deref() {
+        None | Some("") => LogLevel::Warn,
+        Some(level) => LogLevel::parse(level)
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("Invalid level: {}", level)))?,
+    };
+    
+    let groups = state.groups.read().await;
+    Ok(Json(GroupsResponse {
+        total_groups: groups.len(),
+        groups: groups.list(&min_level, params.sort.unwrap_or_default(), params.limit.unwrap_or(100)),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PatternsParams {
+    sort: Option<PatternSort>,
+    limit: Option<usize>,
+    /// Window for counting newly learned patterns, e.g. `5m` (default)
+    new_within: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PatternsResponse {
+    total_patterns: usize,
+    new_patterns: usize,
+    new_within_seconds: i64,
+    patterns: Vec<Pattern>,
+}
+
+async fn patterns_handler(
+    State(state): State<AppState>,
+    Query(params): Query<PatternsParams>,
+) -> Result<Json<PatternsResponse>, (StatusCode, String)> {
+    let new_within = match params.new_within.as_deref() {
+        None | Some("") => chrono::Duration::minutes(5),
+        Some(window) => parse_duration(window)
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid new_within: {}", e)))?,
+    };
+    
+    let patterns = state.patterns.read().await;
+    Ok(Json(PatternsResponse {
+        total_patterns: patterns.len(),
+        new_patterns: patterns.discovered_within(new_within),
+        new_within_seconds: new_within.num_seconds(),
+        patterns: patterns.list(params.sort.unwrap_or_default(), params.limit.unwrap_or(100)),
+    }))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextScope {
+    Source,
+    Logger,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContextParams {
+    before: Option<usize>,
+    after: Option<usize>,
+    /// Only include neighbours from the same source or logger as the entry
+    same: Option<ContextScope>,
+    #[serde(flatten)]
+    projection: ProjectionParams,
+}
+
+#[derive(Serialize)]
+struct ContextResponse<'a> {
+    before: Vec<ProjectedEntry<'a>>,
+    entry: ProjectedEntry<'a>,
+    after: Vec<ProjectedEntry<'a>>,
+}
+
+/// Neighbours of an entry in the unfiltered buffer.
+async fn context_handler(
+    State(state): State<AppState>,
+    Path(seq): Path<u64>,
+    Query(params): Query<ContextParams>,
+) -> Result<Response, (StatusCode, String)> {
+    const MAX_CONTEXT: usize = 1000;
+    let before = params.before.unwrap_or(50).min(MAX_CONTEXT);
+    let after = params.after.unwrap_or(50).min(MAX_CONTEXT);
+    let projection = params.projection.projection();
+    
+    let logs = state.logs.read().await;
+    let index = logs
+        .binary_search_by_key(&seq, |entry| entry.seq)
+        .map_err(|_| (StatusCode::NOT_FOUND, format!("Log {} is not in the buffer", seq)))?;
+    let anchor = &logs[index];
+    let in_scope = |entry: &&LogEntry| match params.same {
+        Some(ContextScope::Source) => entry.source == anchor.source,
+        Some(ContextScope::Logger) => entry.logger == anchor.logger,
+        None => true,
+    };
+    
+    let mut before_entries: Vec<ProjectedEntry> = logs[..index]
+        .iter()
+        .rev()
+        .filter(in_scope)
+        .take(before)
+        .map(|entry| projection.view(entry))
+        .collect();
+    before_entries.reverse();
+    let response = ContextResponse {
+        before: before_entries,
+        entry: projection.view(anchor),
+        after: logs[index + 1..]
+            .iter()
+            .filter(in_scope)
+            .take(after)
+            .map(|entry| projection.view(entry))
+            .collect(),
+    };
+    
+    // Serialize while the read lock is held; the projected views borrow the buffer
+    Ok(Json(response).into_response())
+}
+
+async fn correlation_handler(State(state): State<AppState>) -> Json<CorrelationConfig> {
+    Json(state.correlation.as_ref().clone())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TraceParams {
+    /// Only match this correlation key instead of all configured keys
+    key: Option<String>,
+}
+
+async fn trace_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<TraceParams>,
+) -> Result<Json<Trace>, (StatusCode, String)> {
+    let entries: Vec<LogEntry> = {
+        let logs = state.logs.read().await;
+        logs.iter()
+            .filter(|entry| state.correlation.matches(entry, &id, params.key.as_deref()))
             .cloned()
             .collect()
     };
+    if entries.is_empty() {
+        return Err((StatusCode::NOT_FOUND, format!("No logs found for trace {}", id)));
+    }
     
-    let filtered_count = filtered_logs.len();
+    Ok(Json(build_trace(&id, entries, &state.correlation)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FieldStatsParams {
+    field: String,
+    interval: Option<String>,
+}
+
+async fn field_stats_handler(
+    State(state): State<AppState>,
+    Query(params): Query<LogQueryParams>,
+    Query(stats_params): Query<FieldStatsParams>,
+) -> Result<Json<FieldStats>, (StatusCode, String)> {
+    let filter = params
+        .to_filter(&state)
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
     
-    // Apply limit
-    let logs = if let Some(limit) = params.limit {
-        filtered_logs.into_iter().rev().take(limit).collect::<Vec<_>>().into_iter().rev().collect()
-    } else {
-        filtered_logs
+    // Without an interval a single summary is returned
+    let bucketing = match stats_params.interval.as_deref() {
+        None | Some("") => None,
+        Some("auto") => Some(None),
+        Some(interval) => Some(Some(
+            parse_duration(interval)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid interval: {}", e)))?,
+        )),
     };
     
-    Json(LogResponse {
-        logs,
-        total_count,
-        filtered_count,
-    })
+    let logs = state.logs.read().await;
+    let result = field_stats(
+        logs.iter().filter(|entry| filter.matches(entry)),
+        &stats_params.field,
+        bucketing,
+        filter.start_time,
+        filter.end_time,
+    )
+    .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    
+    Ok(Json(result))
 }
 
 async fn clear_logs_handler(State(state): State<AppState>) -> StatusCode {
@@ -379,12 +1313,54 @@ async fn clear_logs_handler(State(state): State<AppState>) -> StatusCode {
 
 async fn get_stats_handler(State(state): State<AppState>) -> Json<StatsResponse> {
     let log_count = state.logs.read().await.len();
-    let connection_count = state.connection_count.load(Ordering::Relaxed);
-    
+
     Json(StatsResponse {
         total_logs: log_count,
-        active_connections: connection_count,
-        uptime_seconds: 0, // TODO: Track actual uptime
+        active_connections: state.clients.len(),
+        uptime_seconds: (Utc::now() - state.started_at).num_seconds().max(0) as u64,
+    })
+}
+
+/// Prometheus metrics in the text exposition format.
+async fn metrics_handler(State(state): State<AppState>) -> Response {
+    let mut out = MetricsText::new();
+    state.metrics.write_to(&mut out);
+
+    out.gauge("jsonweblog_buffer_entries", "Entries currently in the buffer.", state.logs.read().await.len() as f64);
+    out.gauge("jsonweblog_buffer_capacity", "Entries the buffer keeps before evicting.", state.max_entries as f64);
+    out.gauge(
+        "jsonweblog_broadcast_pending_entries",
+        "Entries in the broadcast channel not yet received by every client.",
+        state.log_tx.len() as f64,
+    );
+
+    let clients = state.clients.list(0);
+    out.family("jsonweblog_clients", "Connected streaming clients by protocol.", "gauge");
+    for (kind, label) in [(ClientKind::WebSocket, "websocket"), (ClientKind::EventStream, "sse")] {
+        let count = clients.iter().filter(|client| client.kind == kind).count();
+        out.sample("jsonweblog_clients", &[("kind", label)], count as f64);
+    }
+    out.gauge(
+        "jsonweblog_uptime_seconds",
+        "Seconds since the server started.",
+        (Utc::now() - state.started_at).num_seconds().max(0) as f64,
+    );
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], out.finish()).into_response()
+}
+
+#[derive(Serialize)]
+struct ClientsResponse {
+    total_clients: usize,
+    clients: Vec<ClientInfo>,
+}
+
+async fn clients_handler(State(state): State<AppState>) -> Json<ClientsResponse> {
+    let latest_seq = state.next_seq.load(Ordering::Relaxed).saturating_sub(1);
+    let clients = state.clients.list(latest_seq);
+    Json(ClientsResponse {
+        total_clients: clients.len(),
+        clients,
     })
 }
 
@@ -392,64 +1368,516 @@ async fn get_stats_handler(State(state): State<AppState>) -> Json<StatsResponse>
 async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<AppState>,
+    remote: Option<ConnectInfo<SocketAddr>>,
+    Query(params): Query<LogQueryParams>,
+    Query(stream): Query<StreamParams>,
 ) -> Response {
-    ws.on_upgrade(|socket| websocket_connection(socket, state))
+    // Reject malformed filters before upgrading so the client gets a readable error
+    let filter = match params.to_live_filter(&state).await {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    let client = state.clients.register(ClientKind::WebSocket, remote.map(|ConnectInfo(addr)| addr), params.describe());
+    let subscription = Subscription::new(client, filter, params.projection());
+    let resume_after = params.resume_after;
+    let encoder = FrameEncoder::new(stream.encoding);
+
+    ws.on_upgrade(move |socket| websocket_connection(socket, state, subscription, resume_after, encoder, stream.batch))
 }
 
-async fn websocket_connection(socket: WebSocket, state: AppState) {
-    // let connection_id = state.connection_count.fetch_add(1, Ordering::Relaxed) + 1;
-    // info!("WebSocket connection {} established", connection_id);
-    
-    let (mut sender, mut receiver) = socket.split();
-    let mut log_rx = state.log_tx.subscribe();
-    
-    // Send existing logs to the new client
-    tokio::spawn(async move {
-        let logs = state.logs.read().await;
-        let recent_logs = logs.iter().rev().take(100_000).rev().cloned().collect::<Vec<_>>();
-        drop(logs);
-        
-        for log_entry in recent_logs {
-            if let Ok(json_string) = serde_json::to_string(&log_entry) {
-                if sender.send(Message::Text(json_string)).await.is_err() {
-                    break;
-                }
-            }
+/// Frame format a WebSocket client picks when it connects.
+#[derive(Debug, Default, Deserialize)]
+struct StreamParams {
+    #[serde(default)]
+    encoding: Encoding,
+    /// Send entries in array frames instead of one frame per entry
+    #[serde(default)]
+    batch: bool,
+}
+
+/// Messages a WebSocket client can send.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// Replaces the connection's filter and projection, using the `/api/logs` parameters.
+    /// The server answers with `subscribed` and a fresh backfill.
+    Subscribe(LogQueryParams),
+}
+
+/// Control messages sent to WebSocket clients. Log entries are sent as they are (or in
+/// array frames when batched) and control messages are objects told apart by `type`.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+    /// A new subscription took effect; entries matching it follow. When `resumed` is
+    /// false the client should discard what it has, as the full backfill is resent.
+    Subscribed { resumed: bool },
+    /// Entries `first_seq..=last_seq` were evicted before they could be sent, so some
+    /// matching entries may be missing
+    Gap { first_seq: u64, last_seq: u64 },
+    /// All buffered entries matching the subscription have been sent
+    BackfillComplete { count: usize },
+    /// The client fell behind the live stream by `skipped` entries; the missed entries
+    /// that match follow from the buffer
+    Lagged { skipped: u64 },
+    /// A client message was rejected; the previous subscription stays active
+    Error { message: String },
+    /// Incoming logs changed shape
+    SchemaChange { changes: &'a [SchemaChange] },
+}
+
+impl ServerMessage<'_> {
+    /// The serialized `type`, used as the event name over Server-Sent Events.
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Subscribed { .. } => "subscribed",
+            Self::Gap { .. } => "gap",
+            Self::BackfillComplete { .. } => "backfill_complete",
+            Self::Lagged { .. } => "lagged",
+            Self::Error { .. } => "error",
+            Self::SchemaChange { .. } => "schema_change",
         }
-        
-        // Forward new log entries
-        while let Ok(json_string) = log_rx.recv().await {
-            if sender.send(Message::Text(json_string)).await.is_err() {
-                break;
-            }
+    }
+}
+
+/// Messages queued per client before the connection waits for its socket to drain.
+/// While it waits the broadcast receiver falls behind, which is handled as lag.
+const SEND_QUEUE_CAPACITY: usize = 256;
+
+/// How long a partial batch of live entries waits for more before it is sent.
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The client went away and its queue no longer accepts messages.
+#[derive(Debug)]
+struct Disconnected;
+
+impl<T> From<mpsc::error::SendError<T>> for Disconnected {
+    fn from(_: mpsc::error::SendError<T>) -> Self {
+        Disconnected
+    }
+}
+
+type SendResult<T = ()> = Result<T, Disconnected>;
+
+/// Where a subscription delivers entries and control messages.
+trait StreamOutput {
+    async fn push_entry(&mut self, entry: &LogEntry, projection: &Projection) -> SendResult;
+    async fn send_control(&mut self, message: &ServerMessage<'_>) -> SendResult;
+}
+
+/// Writes queued messages to the socket until the queue closes or a write fails.
+async fn write_messages<W: tokio::io::AsyncWrite + Unpin>(
+    mut sender: crate::websocket::Sender<W>,
+    mut queue: mpsc::Receiver<Message>,
+) {
+    while let Some(message) = queue.recv().await {
+        if sender.send(message).await.is_err() {
+            return;
         }
-    });
-    
-    // Handle incoming messages (for potential future features)
-    while let Some(msg) = receiver.next().await {
-        match msg {
-            Ok(Message::Text(_)) => {
-                // Handle text messages if needed
+    }
+    let _ = sender.close().await;
+}
+
+/// Outgoing side of a connection. Entries are batched into frames in the negotiated
+/// encoding and queued for the writer task; sending fails once the socket is gone.
+struct Outbox {
+    queue: mpsc::Sender<Message>,
+    encoder: FrameEncoder,
+    /// Pending entries when the client asked for batches
+    batch: Option<Batch>,
+    /// When the pending batch is sent even if it is not full
+    deadline: Option<Instant>,
+}
+
+impl Outbox {
+    fn new(queue: mpsc::Sender<Message>, encoder: FrameEncoder, batched: bool) -> Self {
+        Self {
+            queue,
+            encoder,
+            batch: batched.then(Batch::default),
+            deadline: None,
+        }
+    }
+
+    async fn send_frame(&self, frame: Frame) -> SendResult {
+        let message = match frame {
+            Frame::Text(text) => Message::Text(text),
+            Frame::Binary(bytes) => Message::Binary(bytes),
+        };
+        Ok(self.queue.send(message).await?)
+    }
+
+    async fn flush(&mut self) -> SendResult {
+        self.deadline = None;
+        let Some(batch) = self.batch.as_mut().filter(|batch| !batch.is_empty()) else {
+            return Ok(());
+        };
+        match batch.take_frame(&self.encoder) {
+            Ok(frame) => self.send_frame(frame).await,
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+impl StreamOutput for Outbox {
+    /// Sends an entry as its own frame, or adds it to the pending batch and sends the
+    /// batch once it is full.
+    async fn push_entry(&mut self, entry: &LogEntry, projection: &Projection) -> SendResult {
+        let Ok(item) = self.encoder.encode(&projection.view(entry)) else {
+            return Ok(());
+        };
+        let Some(batch) = self.batch.as_mut() else {
+            return match self.encoder.frame(item) {
+                Ok(frame) => self.send_frame(frame).await,
+                Err(_) => Ok(()),
+            };
+        };
+        batch.push(item);
+        self.deadline.get_or_insert_with(|| Instant::now() + BATCH_INTERVAL);
+        if batch.is_full() {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Sends a control message after the pending entries, keeping their order.
+    async fn send_control(&mut self, message: &ServerMessage<'_>) -> SendResult {
+        self.flush().await?;
+        match self.encoder.encode(message).and_then(|payload| self.encoder.frame(payload)) {
+            Ok(frame) => self.send_frame(frame).await,
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+/// Resolves when a pending batch is due; never resolves without one.
+async fn batch_due(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Which entries a streaming client receives and how they are serialized.
+struct Subscription {
+    filter: LogFilter,
+    projection: Projection,
+    /// Highest sequence ID sent, so broadcasts overlapping the backfill are skipped
+    last_sent_seq: u64,
+    /// Highest sequence ID examined, where a resync after broadcast lag continues
+    last_seen_seq: u64,
+    client: ClientHandle,
+}
+
+impl Subscription {
+    fn new(client: ClientHandle, filter: LogFilter, projection: Projection) -> Self {
+        Self {
+            filter,
+            projection,
+            last_sent_seq: 0,
+            last_seen_seq: 0,
+            client,
+        }
+    }
+
+    /// Switches to a new filter and projection, starting over from an empty position.
+    fn reset(&mut self, filter: LogFilter, projection: Projection, description: serde_json::Value) {
+        self.filter = filter;
+        self.projection = projection;
+        self.last_sent_seq = 0;
+        self.last_seen_seq = 0;
+        self.client.set_filter(description);
+    }
+
+    fn record_seen(&mut self, seq: u64) {
+        self.last_seen_seq = self.last_seen_seq.max(seq);
+        self.client.record_seen(seq);
+    }
+
+    async fn send_entry<O: StreamOutput>(&mut self, entry: &LogEntry, out: &mut O) -> SendResult {
+        out.push_entry(entry, &self.projection).await?;
+        self.last_sent_seq = self.last_sent_seq.max(entry.seq);
+        self.client.record_sent();
+        Ok(())
+    }
+
+    /// Forwards a broadcast entry if it matches and was not already sent.
+    /// Folded repeats arrive again with a known `seq` and are forwarded as updates.
+    async fn forward<O: StreamOutput>(&mut self, entry: &LogEntry, out: &mut O) -> SendResult {
+        if (entry.seq > self.last_sent_seq || entry.repeat.is_some()) && self.filter.matches(entry) {
+            self.send_entry(entry, out).await?;
+        }
+        self.record_seen(entry.seq);
+        Ok(())
+    }
+
+    /// Sends the buffered entries after `last_seen_seq` that match the filter. The buffer
+    /// is read in chunks so neither the lock nor the copied entries are held for long.
+    /// Once a position is known, entries evicted past it are reported as a gap.
+    async fn backfill<O: StreamOutput>(&mut self, state: &AppState, out: &mut O) -> SendResult<usize> {
+        const SCAN_CHUNK: usize = 10_000;
+
+        let mut cursor = self.last_seen_seq;
+        let mut count = 0;
+        loop {
+            let (gap, chunk) = {
+                let logs = state.logs.read().await;
+                let start = logs.partition_point(|entry| entry.seq <= cursor);
+                let scanned = &logs[start..logs.len().min(start + SCAN_CHUNK)];
+                // Sequence IDs are assigned under the write lock, so the buffer is contiguous
+                // and anything between the cursor and the first buffered entry is gone
+                let first_available = scanned
+                    .first()
+                    .map(|entry| entry.seq)
+                    .unwrap_or_else(|| state.next_seq.load(Ordering::Relaxed));
+                let gap = (cursor > 0 && first_available > cursor + 1).then(|| ServerMessage::Gap {
+                    first_seq: cursor + 1,
+                    last_seq: first_available - 1,
+                });
+                cursor = scanned.last().map_or(first_available - 1, |entry| entry.seq);
+                let chunk: Vec<LogEntry> =
+                    scanned.iter().filter(|entry| self.filter.matches(entry)).cloned().collect();
+                (gap, (!scanned.is_empty()).then_some(chunk))
+            };
+            if let Some(gap) = &gap {
+                out.send_control(gap).await?;
             }
-            Ok(Message::Close(_)) => {
-                break;
+            self.record_seen(cursor);
+            let Some(chunk) = chunk else { break };
+            for entry in &chunk {
+                self.send_entry(entry, out).await?;
             }
-            Err(e) => {
-                error!("WebSocket error: {}", e);
-                break;
+            count += chunk.len();
+        }
+        Ok(count)
+    }
+
+    /// Sends `subscribed`, the backfill and `backfill_complete`. With `resume_after` only
+    /// entries after that sequence ID are sent, unless the server never assigned it (for
+    /// example after a restart) and the client has to start over.
+    async fn start<O: StreamOutput>(
+        &mut self,
+        state: &AppState,
+        out: &mut O,
+        resume_after: Option<u64>,
+    ) -> SendResult {
+        let resume_after = resume_after.filter(|&seq| seq < state.next_seq.load(Ordering::Relaxed));
+        out.send_control(&ServerMessage::Subscribed { resumed: resume_after.is_some() }).await?;
+
+        if let Some(seq) = resume_after {
+            self.last_sent_seq = seq;
+            self.record_seen(seq);
+            // The client's last entry may have absorbed repeats since it was sent
+            let folded = {
+                let logs = state.logs.read().await;
+                logs.binary_search_by_key(&seq, |entry| entry.seq)
+                    .ok()
+                    .map(|index| &logs[index])
+                    .filter(|entry| entry.repeat.is_some() && self.filter.matches(entry))
+                    .cloned()
+            };
+            if let Some(entry) = folded {
+                self.send_entry(&entry, out).await?;
             }
-            _ => {}
         }
+
+        let count = self.backfill(state, out).await?;
+        out.send_control(&ServerMessage::BackfillComplete { count }).await
+    }
+
+    /// Catches up from the buffer after the live stream skipped `skipped` entries.
+    async fn resync<O: StreamOutput>(&mut self, state: &AppState, out: &mut O, skipped: u64) -> SendResult {
+        self.client.record_skipped(skipped);
+        state.metrics.record_lagged(skipped);
+        out.send_control(&ServerMessage::Lagged { skipped }).await?;
+        self.backfill(state, out).await.map(|_| ())
+    }
+
+    async fn apply<O: StreamOutput>(&mut self, update: LiveUpdate, state: &AppState, out: &mut O) -> SendResult {
+        match update {
+            LiveUpdate::Entry(entry) => self.forward(&entry, out).await,
+            LiveUpdate::Lagged(skipped) => self.resync(state, out, skipped).await,
+            LiveUpdate::SchemaChange(changes) => {
+                out.send_control(&ServerMessage::SchemaChange { changes: &changes }).await
+            }
+        }
+    }
+}
+
+/// Something broadcast to every subscription.
+enum LiveUpdate {
+    Entry(Arc<LogEntry>),
+    /// The entry receiver fell behind by this many entries
+    Lagged(u64),
+    SchemaChange(Arc<Vec<SchemaChange>>),
+}
+
+/// Waits for the next broadcast; `None` once the server is shutting down. Schema
+/// changes that lag behind are dropped, as the catalog can always be refetched.
+async fn next_update(
+    log_rx: &mut broadcast::Receiver<Arc<LogEntry>>,
+    schema_rx: &mut broadcast::Receiver<Arc<Vec<SchemaChange>>>,
+) -> Option<LiveUpdate> {
+    loop {
+        tokio::select! {
+            biased;
+            log_entry = log_rx.recv() => return match log_entry {
+                Ok(log_entry) => Some(LiveUpdate::Entry(log_entry)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => Some(LiveUpdate::Lagged(skipped)),
+                Err(broadcast::error::RecvError::Closed) => None,
+            },
+            changes = schema_rx.recv() => match changes {
+                Ok(changes) => return Some(LiveUpdate::SchemaChange(changes)),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            },
+        }
+    }
+}
+
+async fn handle_client_message<O: StreamOutput>(
+    text: &str,
+    state: &AppState,
+    subscription: &mut Subscription,
+    out: &mut O,
+) -> SendResult {
+    let params = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(ClientMessage::Subscribe(params)) => params,
+        Err(e) => {
+            let message = format!("Invalid message: {}", e);
+            return out.send_control(&ServerMessage::Error { message }).await;
+        }
+    };
+    match params.to_live_filter(state).await {
+        Ok(filter) => {
+            subscription.reset(filter, params.projection(), params.describe());
+            subscription.start(state, out, params.resume_after).await
+        }
+        Err(e) => out.send_control(&ServerMessage::Error { message: e.to_string() }).await,
+    }
+}
+
+async fn websocket_connection(
+    socket: WebSocket,
+    state: AppState,
+    mut subscription: Subscription,
+    resume_after: Option<u64>,
+    encoder: FrameEncoder,
+    batched: bool,
+) {
+    let (sink, mut receiver) = socket.split();
+    let (queue, queued) = mpsc::channel(SEND_QUEUE_CAPACITY);
+    let mut out = Outbox::new(queue, encoder, batched);
+    tokio::spawn(write_messages(sink, queued));
+    // Subscribe before the backfill so nothing ingested meanwhile is missed
+    let mut log_rx = state.log_tx.subscribe();
+    let mut schema_rx = state.schema_tx.subscribe();
+
+    let mut result = subscription.start(&state, &mut out, resume_after).await;
+    while result.is_ok() {
+        result = tokio::select! {
+            biased;
+            message = receiver.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    handle_client_message(&text, &state, &mut subscription, &mut out).await
+                }
+                // Pings are answered through the queue, after what is already waiting
+                Some(Ok(Message::Ping(payload))) => out.queue.send(Message::Pong(payload)).await.map_err(Disconnected::from),
+                Some(Ok(Message::Close)) | None => break,
+                Some(Err(e)) => {
+                    error!("WebSocket error: {}", e);
+                    break;
+                }
+                Some(Ok(_)) => Ok(()),
+            },
+            _ = batch_due(out.deadline) => out.flush().await,
+            update = next_update(&mut log_rx, &mut schema_rx) => match update {
+                Some(update) => subscription.apply(update, &state, &mut out).await,
+                None => break,
+            },
+        };
+    }
+
+    // The writer flushes what is queued and closes the socket once the queue is dropped;
+    // dropping the subscription unregisters the client
+}
+
+/// Server-Sent Events output. Entries carry their sequence ID as the event ID and
+/// control messages are sent as events named after their `type`.
+struct EventOutput {
+    events: mpsc::Sender<Result<Event, Infallible>>,
+}
+
+impl StreamOutput for EventOutput {
+    async fn push_entry(&mut self, entry: &LogEntry, projection: &Projection) -> SendResult {
+        if let Ok(data) = projection.to_json_string(entry) {
+            let event = Event::default().id(entry.seq.to_string()).data(data);
+            self.events.send(Ok(event)).await?;
+        }
+        Ok(())
+    }
+
+    async fn send_control(&mut self, message: &ServerMessage<'_>) -> SendResult {
+        if let Ok(data) = serde_json::to_string(message) {
+            self.events.send(Ok(Event::default().event(message.kind()).data(data))).await?;
+        }
+        Ok(())
+    }
+}
+
+// Server-Sent Events handler
+async fn stream_handler(
+    State(state): State<AppState>,
+    remote: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Query(params): Query<LogQueryParams>,
+) -> Response {
+    let filter = match params.to_live_filter(&state).await {
+        Ok(filter) => filter,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    // EventSource sends the ID of the last event it received when it reconnects
+    let resume_after = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .or(params.resume_after);
+
+    let (events, stream) = mpsc::channel(SEND_QUEUE_CAPACITY);
+    let client = state.clients.register(ClientKind::EventStream, remote.map(|ConnectInfo(addr)| addr), params.describe());
+    let subscription = Subscription::new(client, filter, params.projection());
+    tokio::spawn(event_stream(state, subscription, resume_after, EventOutput { events }));
+
+    Sse::new(ReceiverStream::new(stream))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn event_stream(state: AppState, mut subscription: Subscription, resume_after: Option<u64>, mut out: EventOutput) {
+    let mut log_rx = state.log_tx.subscribe();
+    let mut schema_rx = state.schema_tx.subscribe();
+
+    let mut result = subscription.start(&state, &mut out, resume_after).await;
+    while result.is_ok() {
+        result = tokio::select! {
+            biased;
+            // Notice a disconnect even while nothing matches the filter
+            _ = out.events.closed() => break,
+            update = next_update(&mut log_rx, &mut schema_rx) => match update {
+                Some(update) => subscription.apply(update, &state, &mut out).await,
+                None => break,
+            },
+        };
     }
-    
-    state.connection_count.fetch_sub(1, Ordering::Relaxed);
-    // info!("WebSocket connection {} closed", connection_id);
 }
 
 // Schema API handlers
-async fn get_schema_handler(State(state): State<AppState>) -> Json<Schema> {
+async fn get_schema_handler(State(state): State<AppState>) -> Response {
+    // Serialize under the lock; cloning would copy every tracked distinct-value set
     let schema = state.schema.read().await;
-    Json(schema.clone())
+    Json(&*schema).into_response()
 }
 
 async fn get_columns_handler(State(state): State<AppState>) -> Json<Option<TableConfig>> {
@@ -475,8 +1903,7 @@ async fn set_columns_handler(
     };
     
     // Save to file
-    let settings_path = TableConfig::get_settings_path();
-    if let Err(e) = config.save_to_file(&settings_path) {
+    if let Err(e) = config.save_to_file(&state.settings_path) {
         warn!("Failed to save table configuration: {}", e);
     } /* else {
         info!("Saved table configuration to {:?}", settings_path);
@@ -486,4 +1913,387 @@ async fn set_columns_handler(
     // info!("Updated table configuration with {} columns", config.columns.len());
     
     Json(config)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(line: u64, level: LogLevel, message: &str) -> LogEntry {
+        LogEntry::new(line, Utc::now(), level, "app".to_string(), message.to_string())
+    }
+
+    #[test]
+    fn test_parses_bind_addresses() {
+        let parse = |s: &str| s.parse::<BindAddress>();
+        assert_eq!(parse("127.0.0.1").unwrap(), BindAddress::default());
+        assert_eq!(parse("localhost").unwrap(), BindAddress::default());
+        assert_eq!(parse("[::1]").unwrap(), BindAddress::Ip(IpAddr::V6(Ipv6Addr::LOCALHOST)));
+        assert_eq!(parse("::").unwrap(), BindAddress::Ip(IpAddr::V6(Ipv6Addr::UNSPECIFIED)));
+        assert_eq!(parse("unix:app.sock").unwrap(), BindAddress::Unix("app.sock".into()));
+        assert_eq!(parse("/run/jsonweblog.sock").unwrap(), BindAddress::Unix("/run/jsonweblog.sock".into()));
+        assert!(parse("unix:").is_err());
+        assert!(parse("example.com").is_err());
+        assert_eq!(BindAddress::Unix("./a.sock".into()).to_string(), "unix:./a.sock");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_removed_when_cancelled() {
+        let dir = std::env::temp_dir().join(format!("jsonweblog-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("app.jsonl");
+        std::fs::write(&input, "{\"message\": \"hello\"}\n").unwrap();
+        let socket = dir.join("jsonweblog.sock");
+
+        let server = WebServer::new(0)
+            .with_bind_address(BindAddress::Unix(socket.clone()))
+            .with_input_file(input);
+        // Like Ctrl+C in main, stop the server by dropping its future
+        let serving = tokio::spawn(async move { server.start().await });
+        while !socket.exists() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        serving.abort();
+        let _ = serving.await;
+
+        assert!(!socket.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reachable_urls() {
+        assert_eq!(reachable_urls(IpAddr::V4(Ipv4Addr::LOCALHOST), 3000), ["http://127.0.0.1:3000"]);
+        assert_eq!(reachable_urls(IpAddr::V6(Ipv6Addr::LOCALHOST), 3000), ["http://[::1]:3000"]);
+
+        let urls = reachable_urls(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 8080);
+        assert_eq!(urls[..2], ["http://127.0.0.1:8080", "http://[::1]:8080"]);
+        assert!(!urls.iter().any(|url| url.contains("[fe80")));
+        let unique: std::collections::HashSet<_> = urls.iter().collect();
+        assert_eq!(unique.len(), urls.len());
+    }
+
+    #[tokio::test]
+    async fn test_unspecified_ipv6_accepts_ipv4() {
+        let listener = bind_tcp(SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::net::TcpStream::connect((Ipv4Addr::LOCALHOST, port)).await.unwrap();
+        listener.accept().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_collapse_repeats() {
+        let mut state = AppState::new();
+        state.collapse_repeats = true;
+        let mut rx = state.log_tx.subscribe();
+
+        state.add_logs_batch(vec![
+            entry(1, LogLevel::Info, "starting"),
+            entry(2, LogLevel::Warn, "retry 1 failed"),
+        ]).await;
+        state.add_logs_batch(vec![
+            entry(3, LogLevel::Warn, "retry 2 failed"),
+            entry(4, LogLevel::Warn, "retry 3 failed"),
+            entry(5, LogLevel::Info, "gave up"),
+        ]).await;
+
+        let logs = state.logs.read().await;
+        assert_eq!(logs.iter().map(|entry| entry.seq).collect::<Vec<_>>(), vec![1, 2, 3]);
+        let repeat = logs[1].repeat.as_ref().unwrap();
+        assert_eq!(repeat.count, 3);
+        assert_eq!(repeat.last_line, 4);
+        assert_eq!(repeat.samples.len(), 2);
+        assert_eq!(state.groups.read().await.list(&LogLevel::Warn, GroupSort::Count, 1)[0].count, 3);
+
+        // The folded entry is broadcast again after the second batch
+        let mut received = Vec::new();
+        while let Ok(entry) = rx.try_recv() {
+            received.push((entry.seq, entry.repeat.as_ref().map_or(1, |repeat| repeat.count)));
+        }
+        assert_eq!(received, vec![(1, 1), (2, 1), (2, 3), (3, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_repeats_from_other_loggers_are_kept() {
+        let mut state = AppState::new();
+        state.collapse_repeats = true;
+        let mut other_logger = entry(2, LogLevel::Warn, "retry 1 failed");
+        other_logger.logger = "worker".to_string();
+        let other_module = entry(3, LogLevel::Warn, "retry 2 failed").with_module("db".to_string());
+        let other_source = entry(4, LogLevel::Warn, "retry 3 failed").with_source("other.log");
+
+        state.add_logs_batch(vec![
+            entry(1, LogLevel::Warn, "retry 0 failed"),
+            other_logger,
+            other_module,
+            other_source,
+        ]).await;
+
+        let logs = state.logs.read().await;
+        assert_eq!(logs.len(), 4);
+        assert!(logs.iter().all(|entry| entry.repeat.is_none()));
+    }
+
+    async fn page(state: &AppState, query: serde_json::Value) -> Result<serde_json::Value, (StatusCode, String)> {
+        let params = serde_json::from_value::<LogQueryParams>(query).unwrap();
+        let response = query_logs(state, params).await?;
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        Ok(serde_json::from_slice(&body).unwrap())
+    }
+
+    fn seqs(page: &serde_json::Value) -> Vec<u64> {
+        page["logs"].as_array().unwrap().iter().map(|entry| entry["seq"].as_u64().unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_log_paging() {
+        let state = AppState::new();
+        state.add_logs_batch((1..=5).map(|line| entry(line, LogLevel::Info, "msg")).collect()).await;
+
+        // A bare limit returns the tail and pages backwards with `before`
+        let tail = page(&state, serde_json::json!({"limit": 2})).await.unwrap();
+        assert_eq!(seqs(&tail), [4, 5]);
+        assert_eq!(tail["next_cursor"], 4);
+        let older = page(&state, serde_json::json!({"limit": 2, "before": 4})).await.unwrap();
+        assert_eq!(seqs(&older), [2, 3]);
+        let oldest = page(&state, serde_json::json!({"limit": 2, "before": 2})).await.unwrap();
+        assert_eq!(seqs(&oldest), [1]);
+        assert!(oldest["next_cursor"].is_null());
+
+        // `after` and `offset` page forwards from the oldest entry
+        let forward = page(&state, serde_json::json!({"limit": 2, "after": 1})).await.unwrap();
+        assert_eq!(seqs(&forward), [2, 3]);
+        assert_eq!(forward["next_cursor"], 3);
+        let skipped = page(&state, serde_json::json!({"limit": 2, "offset": 3})).await.unwrap();
+        assert_eq!(seqs(&skipped), [4, 5]);
+        assert!(skipped["next_cursor"].is_null());
+
+        let descending = page(&state, serde_json::json!({"limit": 2, "order": "desc"})).await.unwrap();
+        assert_eq!(seqs(&descending), [5, 4]);
+        assert_eq!(descending["next_cursor"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_sort_by_field() {
+        let state = AppState::new();
+        let with_ms = |line: u64, ms: Option<u64>| {
+            let fields = ms.map(|ms| [("ms".to_string(), serde_json::json!(ms))].into()).unwrap_or_default();
+            entry(line, LogLevel::Info, "msg").with_raw_fields(fields)
+        };
+        state.add_logs_batch(vec![with_ms(1, Some(30)), with_ms(2, None), with_ms(3, Some(10)), with_ms(4, Some(20))]).await;
+
+        let ascending = page(&state, serde_json::json!({"sort": "ms"})).await.unwrap();
+        assert_eq!(seqs(&ascending), [3, 4, 1, 2]);
+        // Entries without the field stay last when descending too
+        let descending = page(&state, serde_json::json!({"sort": "ms", "order": "desc", "limit": 3})).await.unwrap();
+        assert_eq!(seqs(&descending), [1, 4, 3]);
+        assert!(descending["next_cursor"].is_null());
+        let rest = page(&state, serde_json::json!({"sort": "ms", "order": "desc", "offset": 3})).await.unwrap();
+        assert_eq!(seqs(&rest), [2]);
+
+        let (status, _) = page(&state, serde_json::json!({"sort": "ms", "after": 1})).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_context_around_entry() {
+        let mut state = AppState::new();
+        state.max_entries = 8;
+        state.add_logs_batch((1..=10).map(|line| entry(line, LogLevel::Info, "msg")).collect()).await;
+
+        let context = |uri: &str| {
+            let Query(params) = Query::<ContextParams>::try_from_uri(&uri.parse().unwrap()).unwrap();
+            params
+        };
+        let response = context_handler(State(state.clone()), Path(6), Query(context("/?before=2&after=10&fields=seq,line")))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["before"], serde_json::json!([{"seq": 4, "line": 4}, {"seq": 5, "line": 5}]));
+        assert_eq!(json["entry"], serde_json::json!({"seq": 6, "line": 6}));
+        assert_eq!(json["after"].as_array().unwrap().len(), 4);
+
+        let with_dynamic = context_handler(State(state.clone()), Path(6), Query(context("/?before=0&after=0&include_dynamic=true")))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(with_dynamic.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("\"dynamic_fields\""));
+
+        // Entries 1 and 2 were evicted
+        let (status, _) = context_handler(State(state), Path(2), Query(context("/"))).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    /// Stream output that records entries as `{"seq": N}` and control messages as sent.
+    #[derive(Default)]
+    struct RecordedOutput {
+        sent: Vec<serde_json::Value>,
+    }
+
+    impl StreamOutput for RecordedOutput {
+        async fn push_entry(&mut self, entry: &LogEntry, _projection: &Projection) -> SendResult {
+            self.sent.push(serde_json::json!({"seq": entry.seq}));
+            Ok(())
+        }
+
+        async fn send_control(&mut self, message: &ServerMessage<'_>) -> SendResult {
+            self.sent.push(serde_json::to_value(message).unwrap());
+            Ok(())
+        }
+    }
+
+    fn subscription(state: &AppState) -> Subscription {
+        let client = state.clients.register(ClientKind::WebSocket, None, serde_json::Value::Null);
+        Subscription::new(client, LogFilter::new(), Projection::default())
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_message_replaces_filter() {
+        let state = AppState::new();
+        state.add_logs_batch(vec![
+            entry(1, LogLevel::Info, "started"),
+            entry(2, LogLevel::Error, "failed"),
+            entry(3, LogLevel::Info, "retried"),
+        ]).await;
+        let mut subscription = subscription(&state);
+        let mut out = RecordedOutput::default();
+        subscription.start(&state, &mut out, None).await.unwrap();
+        assert_eq!(out.sent.len(), 5);
+
+        let mut out = RecordedOutput::default();
+        handle_client_message(r#"{"type": "subscribe", "q": "level>=error"}"#, &state, &mut subscription, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(out.sent, [
+            serde_json::json!({"type": "subscribed", "resumed": false}),
+            serde_json::json!({"seq": 2}),
+            serde_json::json!({"type": "backfill_complete", "count": 1}),
+        ]);
+
+        // A rejected message keeps the previous subscription
+        let mut out = RecordedOutput::default();
+        handle_client_message(r#"{"type": "subscribe", "q": "level>="}"#, &state, &mut subscription, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(out.sent.len(), 1);
+        assert_eq!(out.sent[0]["type"], "error");
+
+        let mut live = entry(4, LogLevel::Info, "ignored");
+        live.seq = 4;
+        let mut failed = entry(5, LogLevel::Error, "failed again");
+        failed.seq = 5;
+        let mut out = RecordedOutput::default();
+        subscription.forward(&live, &mut out).await.unwrap();
+        subscription.forward(&failed, &mut out).await.unwrap();
+        assert_eq!(out.sent, [serde_json::json!({"seq": 5})]);
+    }
+
+    #[tokio::test]
+    async fn test_resume_reports_evicted_entries_as_gap() {
+        let mut state = AppState::new();
+        state.max_entries = 3;
+        state.add_logs_batch((1..=6).map(|line| entry(line, LogLevel::Info, "msg")).collect()).await;
+
+        let mut out = RecordedOutput::default();
+        subscription(&state).start(&state, &mut out, Some(1)).await.unwrap();
+        assert_eq!(out.sent, [
+            serde_json::json!({"type": "subscribed", "resumed": true}),
+            serde_json::json!({"type": "gap", "first_seq": 2, "last_seq": 3}),
+            serde_json::json!({"seq": 4}),
+            serde_json::json!({"seq": 5}),
+            serde_json::json!({"seq": 6}),
+            serde_json::json!({"type": "backfill_complete", "count": 3}),
+        ]);
+
+        // Nothing is missing after the newest entry; an unknown cursor starts over
+        let mut out = RecordedOutput::default();
+        subscription(&state).start(&state, &mut out, Some(6)).await.unwrap();
+        assert_eq!(out.sent, [
+            serde_json::json!({"type": "subscribed", "resumed": true}),
+            serde_json::json!({"type": "backfill_complete", "count": 0}),
+        ]);
+        let mut out = RecordedOutput::default();
+        subscription(&state).start(&state, &mut out, Some(99)).await.unwrap();
+        assert_eq!(out.sent[0], serde_json::json!({"type": "subscribed", "resumed": false}));
+        assert_eq!(out.sent.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_lagged_stream_resyncs_from_buffer() {
+        let state = AppState::new();
+        let mut subscription = subscription(&state);
+        let mut log_rx = state.log_tx.subscribe();
+        let mut schema_rx = state.schema_tx.subscribe();
+        let mut out = RecordedOutput::default();
+        subscription.start(&state, &mut out, None).await.unwrap();
+
+        // One batch larger than the broadcast channel overruns the receiver
+        state.add_logs_batch((1..=1100).map(|line| entry(line, LogLevel::Info, "msg")).collect()).await;
+        let mut out = RecordedOutput::default();
+        while let Ok(Some(update)) =
+            tokio::time::timeout(Duration::from_millis(50), next_update(&mut log_rx, &mut schema_rx)).await
+        {
+            subscription.apply(update, &state, &mut out).await.unwrap();
+        }
+
+        assert_eq!(out.sent[0]["type"], "lagged");
+        let skipped = out.sent[0]["skipped"].as_u64().unwrap();
+        assert!(skipped > 0);
+        let seqs: Vec<u64> = out.sent[1..].iter().map(|sent| sent["seq"].as_u64().unwrap()).collect();
+        // Every entry exactly once: the resync sends the buffer, the remaining broadcasts are skipped
+        assert_eq!(seqs, (1..=1100).collect::<Vec<_>>());
+        let mut metrics = MetricsText::new();
+        state.metrics.write_to(&mut metrics);
+        assert!(metrics.finish().contains(&format!("jsonweblog_broadcast_lagged_entries_total {skipped}\n")));
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_resumes_after_last_event_id() {
+        use futures::StreamExt;
+
+        let state = AppState::new();
+        state.add_logs_batch((1..=4).map(|line| entry(line, LogLevel::Info, "msg")).collect()).await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("last-event-id", "2".parse().unwrap());
+        let Query(params) = Query::<LogQueryParams>::try_from_uri(&"/?fields=seq".parse().unwrap()).unwrap();
+        let response = stream_handler(State(state), None, headers, Query(params)).await;
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+        let mut body = response.into_body().into_data_stream();
+        let mut text = String::new();
+        while !text.contains("event: backfill_complete") {
+            let chunk = tokio::time::timeout(Duration::from_secs(1), body.next()).await.unwrap().unwrap().unwrap();
+            text.push_str(&String::from_utf8_lossy(&chunk));
+        }
+        assert!(text.starts_with("event: subscribed\ndata: {\"type\":\"subscribed\",\"resumed\":true}\n\n"));
+        assert!(text.contains("id: 3\ndata: {\"seq\":3}\n\nid: 4\ndata: {\"seq\":4}\n\n"));
+        assert!(!text.contains("id: 2\n"));
+    }
+
+    #[tokio::test]
+    async fn test_around_uses_sequence_ids_and_live_end_is_absolute() {
+        let mut state = AppState::new();
+        state.collapse_repeats = true;
+        let base = Utc::now();
+        let at = |line: u64, message: &str, seconds: i64| {
+            LogEntry::new(line, base + chrono::Duration::seconds(seconds), LogLevel::Info, "app".to_string(), message.to_string())
+        };
+        state.add_logs_batch(vec![
+            at(1, "retry 1", 0),
+            at(2, "retry 2", 1),
+            at(3, "done", 600),
+        ]).await;
+
+        let params = |json: serde_json::Value| serde_json::from_value::<LogQueryParams>(json).unwrap();
+        // Line 3 was stored with sequence ID 2 after line 2 was folded into line 1
+        let filter = params(serde_json::json!({"around": 2, "window": "1m"})).to_filter(&state).await.unwrap();
+        assert_eq!(filter.start_time, Some(base + chrono::Duration::seconds(540)));
+        assert!(params(serde_json::json!({"around": 3})).to_filter(&state).await.is_err());
+
+        assert!(params(serde_json::json!({"end": "now"})).to_filter(&state).await.is_ok());
+        assert!(params(serde_json::json!({"end": "now"})).to_live_filter(&state).await.is_err());
+        assert!(params(serde_json::json!({"start": "now-5m"})).to_live_filter(&state).await.is_ok());
+    }
+}
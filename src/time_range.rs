@@ -0,0 +1,133 @@
+//! Parsing of absolute and relative time expressions used by time-range filters.
+//!
+//! Accepted bounds are RFC 3339 / ISO 8601 timestamps (`2025-07-31T12:00:00Z`),
+//! naive UTC date-times (`2025-07-31 12:00:00`), dates (`2025-07-31`), Unix
+//! timestamps with an explicit unit (`1753963200s`, `1753963200000ms`), and
+//! expressions relative to the current time such as `now`, `now-15m` or `now+1h30m`.
+//! A bare number is rejected, since `2025` could mean a year as well as a timestamp.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+
+/// Parses a time bound, resolving relative expressions against `now`.
+pub fn parse_time_bound(input: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(anyhow!("Time expression is empty"));
+    }
+
+    if let Some(offset) = input.strip_prefix("now") {
+        let offset = offset.trim();
+        if offset.is_empty() {
+            return Ok(now);
+        }
+        let sign = offset.chars().next().unwrap_or('+');
+        let duration = parse_duration(&offset[sign.len_utf8()..])?;
+        let resolved = match sign {
+            '-' => now.checked_sub_signed(duration),
+            '+' => now.checked_add_signed(duration),
+            _ => return Err(anyhow!("Expected '+' or '-' after 'now' in '{}'", input)),
+        };
+        return resolved.ok_or_else(|| anyhow!("Time expression out of range: {}", input));
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(input) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    for format in ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(input, format) {
+            return Ok(dt.and_utc());
+        }
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    let unix = |digits: &str| digits.parse::<i64>().ok();
+    let timestamp = if let Some(millis) = input.strip_suffix("ms").and_then(unix) {
+        Some(DateTime::from_timestamp_millis(millis))
+    } else {
+        input.strip_suffix('s').and_then(unix).map(|secs| DateTime::from_timestamp(secs, 0))
+    };
+    if let Some(dt) = timestamp {
+        return dt.ok_or_else(|| anyhow!("Invalid Unix timestamp: {}", input));
+    }
+
+    if input.parse::<i64>().is_ok() {
+        return Err(anyhow!(
+            "Ambiguous time '{}': add a unit for Unix timestamps (e.g. {}s) or use ISO 8601",
+            input, input
+        ));
+    }
+
+    Err(anyhow!("Unable to parse time expression: {}", input))
+}
+
+/// Parses durations such as `30s`, `15m`, `1h30m`, `2d` or `500ms`.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(anyhow!("Duration is empty"));
+    }
+
+    let mut total = Duration::zero();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(anyhow!("Invalid duration: {}", input));
+        }
+        let value: i64 = rest[..digits]
+            .parse()
+            .map_err(|_| anyhow!("Invalid duration: {}", input))?;
+        rest = &rest[digits..];
+
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let unit = &rest[..unit_len];
+        rest = &rest[unit_len..];
+
+        let part = match unit {
+            "ms" => Duration::try_milliseconds(value),
+            "s" | "" => Duration::try_seconds(value),
+            "m" => Duration::try_minutes(value),
+            "h" => Duration::try_hours(value),
+            "d" => Duration::try_days(value),
+            "w" => Duration::try_weeks(value),
+            _ => return Err(anyhow!("Unknown duration unit '{}' in '{}'", unit, input)),
+        };
+        total = part
+            .and_then(|part| total.checked_add(&part))
+            .ok_or_else(|| anyhow!("Duration out of range: {}", input))?;
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_bounds() {
+        let now = Utc::now();
+        assert_eq!(parse_time_bound("now", now).unwrap(), now);
+        assert_eq!(parse_time_bound("now-15m", now).unwrap(), now - Duration::minutes(15));
+        assert_eq!(parse_time_bound("now+1h30m", now).unwrap(), now + Duration::minutes(90));
+        assert!(parse_time_bound("now*5m", now).is_err());
+    }
+
+    #[test]
+    fn test_absolute_bounds() {
+        let now = Utc::now();
+        let expected = DateTime::parse_from_rfc3339("2025-07-31T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_time_bound("2025-07-31T12:00:00Z", now).unwrap(), expected);
+        assert_eq!(parse_time_bound("2025-07-31 12:00:00", now).unwrap(), expected);
+        assert_eq!(parse_time_bound("1753963200s", now).unwrap(), expected);
+        assert_eq!(parse_time_bound("1753963200000ms", now).unwrap(), expected);
+        assert!(parse_time_bound("1753963200", now).is_err());
+        assert!(parse_time_bound("2025", now).is_err());
+        assert!(parse_time_bound("yesterday", now).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Correlation of log entries that belong to the same trace or request.
+//!
+//! Entries are correlated by configurable key fields such as `trace_id` or
+//! `request_id`. When entries also carry span IDs and parent span IDs, the trace is
+//! returned as a tree of spans; entries without a span are listed at the top level.
+
+use crate::LogEntry;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationConfig {
+    /// Field paths whose value identifies a trace or request
+    pub keys: Vec<String>,
+    pub span_key: String,
+    pub parent_span_key: String,
+}
+
+impl Default for CorrelationConfig {
+    fn default() -> Self {
+        Self {
+            keys: ["trace_id", "request_id", "correlation_id"]
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            span_key: "span_id".to_string(),
+            parent_span_key: "parent_span_id".to_string(),
+        }
+    }
+}
+
+impl CorrelationConfig {
+    /// Uses the given correlation keys, keeping the default span keys.
+    pub fn with_keys(keys: Vec<String>) -> Self {
+        Self {
+            keys,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the entry carries `id` in one of the correlation keys, or only in `key`.
+    pub fn matches(&self, entry: &LogEntry, id: &str, key: Option<&str>) -> bool {
+        match key {
+            Some(key) => entry.get_field_as_string(key).is_some_and(|value| value == id),
+            None => self
+                .keys
+                .iter()
+                .any(|key| entry.get_field_as_string(key).is_some_and(|value| value == id)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Trace {
+    pub id: String,
+    pub count: usize,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub duration_ms: Option<i64>,
+    /// Entries without a span ID, sorted by time
+    pub entries: Vec<LogEntry>,
+    /// Root spans; each holds its own entries and child spans
+    pub spans: Vec<SpanNode>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpanNode {
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub entries: Vec<LogEntry>,
+    pub children: Vec<SpanNode>,
+}
+
+struct SpanEntries {
+    parent: Option<String>,
+    entries: Vec<LogEntry>,
+}
+
+/// Builds the trace for `id` from the matching entries.
+pub fn build_trace(id: &str, mut entries: Vec<LogEntry>, config: &CorrelationConfig) -> Trace {
+    entries.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then(a.seq.cmp(&b.seq)));
+
+    let count = entries.len();
+    let start = entries.first().map(|entry| entry.timestamp);
+    let end = entries.last().map(|entry| entry.timestamp);
+
+    let mut unspanned = Vec::new();
+    let mut span_order = Vec::new();
+    let mut spans: HashMap<String, SpanEntries> = HashMap::new();
+    for entry in entries {
+        let Some(span_id) = entry.get_field_as_string(&config.span_key) else {
+            unspanned.push(entry);
+            continue;
+        };
+        let parent = entry
+            .get_field_as_string(&config.parent_span_key)
+            .filter(|parent| !parent.is_empty() && parent != &span_id);
+        let span = spans.entry(span_id.clone()).or_insert_with(|| {
+            span_order.push(span_id);
+            SpanEntries {
+                parent: None,
+                entries: Vec::new(),
+            }
+        });
+        if span.parent.is_none() {
+            span.parent = parent;
+        }
+        span.entries.push(entry);
+    }
+
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    let mut roots = Vec::new();
+    for span_id in &span_order {
+        match &spans[span_id].parent {
+            Some(parent) if spans.contains_key(parent) => {
+                children.entry(parent.clone()).or_default().push(span_id.clone())
+            }
+            _ => roots.push(span_id.clone()),
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut tree: Vec<SpanNode> = roots
+        .iter()
+        .filter_map(|span_id| build_span(span_id, &mut spans, &children, &mut visited))
+        .collect();
+    // Spans caught in a parent cycle have no root; list them at the top level
+    for span_id in &span_order {
+        if let Some(node) = build_span(span_id, &mut spans, &children, &mut visited) {
+            tree.push(node);
+        }
+    }
+
+    Trace {
+        id: id.to_string(),
+        count,
+        start,
+        end,
+        duration_ms: start.zip(end).map(|(start, end)| (end - start).num_milliseconds()),
+        entries: unspanned,
+        spans: tree,
+    }
+}
+
+fn build_span(
+    span_id: &str,
+    spans: &mut HashMap<String, SpanEntries>,
+    children: &HashMap<String, Vec<String>>,
+    visited: &mut HashSet<String>,
+) -> Option<SpanNode> {
+    if !visited.insert(span_id.to_string()) {
+        return None;
+    }
+    let span = spans.remove(span_id)?;
+
+    let mut nodes: Vec<SpanNode> = children
+        .get(span_id)
+        .into_iter()
+        .flatten()
+        .filter_map(|child| build_span(child, spans, children, visited))
+        .collect();
+    nodes.sort_by_key(|node| node.start);
+
+    // Entries are already sorted, so the span covers its first and last entry
+    let start = span.entries.first().map(|entry| entry.timestamp).unwrap_or_default();
+    let end = span.entries.last().map(|entry| entry.timestamp).unwrap_or_default();
+    Some(SpanNode {
+        span_id: span_id.to_string(),
+        parent_span_id: span.parent,
+        start: nodes.iter().map(|node| node.start).fold(start, DateTime::min),
+        end: nodes.iter().map(|node| node.end).fold(end, DateTime::max),
+        entries: span.entries,
+        children: nodes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogLevel;
+    use chrono::Duration;
+
+    fn entry(offset_ms: i64, fields: serde_json::Value) -> LogEntry {
+        let timestamp = DateTime::parse_from_rfc3339("2025-07-31T12:00:00Z").unwrap().with_timezone(&Utc)
+            + Duration::milliseconds(offset_ms);
+        LogEntry::new(1, timestamp, LogLevel::Info, "app".to_string(), "msg".to_string())
+            .with_raw_fields(serde_json::from_value(fields).unwrap())
+    }
+
+    #[test]
+    fn test_trace_nests_spans() {
+        let config = CorrelationConfig::default();
+        let entries = vec![
+            entry(30, serde_json::json!({"trace_id": "t1", "span_id": "db", "parent_span_id": "root"})),
+            entry(0, serde_json::json!({"trace_id": "t1", "span_id": "root"})),
+            entry(50, serde_json::json!({"trace_id": "t1"})),
+            entry(10, serde_json::json!({"trace_id": "t1", "span_id": "auth", "parent_span_id": "root"})),
+        ];
+        assert!(entries.iter().all(|entry| config.matches(entry, "t1", None)));
+        assert!(!config.matches(&entries[0], "t1", Some("request_id")));
+
+        let trace = build_trace("t1", entries, &config);
+        assert_eq!(trace.count, 4);
+        assert_eq!(trace.duration_ms, Some(50));
+        assert_eq!(trace.entries.len(), 1);
+        assert_eq!(trace.spans.len(), 1);
+
+        let root = &trace.spans[0];
+        assert_eq!(root.span_id, "root");
+        let children: Vec<&str> = root.children.iter().map(|child| child.span_id.as_str()).collect();
+        assert_eq!(children, vec!["auth", "db"]);
+        assert_eq!((root.end - root.start).num_milliseconds(), 30);
+    }
+}
@@ -55,6 +55,9 @@ const HTML_INDEX: &str = r#"<!DOCTYPE html>
                         <option value="monokai-pro">Monokai Pro</option>
                     </select>
                 </div>
+                <button id="facets-btn" class="btn btn-secondary">패싯</button>
+                <button id="problems-btn" class="btn btn-secondary">문제</button>
+                <button id="patterns-btn" class="btn btn-secondary">패턴</button>
                 <button id="columns-btn" class="btn btn-secondary">컬럼 설정</button>
                 <div class="auto-scroll-control">
                     <input type="checkbox" id="auto-scroll-checkbox">
@@ -72,7 +75,24 @@ const HTML_INDEX: &str = r#"<!DOCTYPE html>
             </div>
 
             <div class="filter-group">
-                <input type="text" id="filter-value" placeholder="검색할 값을 입력하세요...">
+                <input type="text" id="filter-value" placeholder="검색할 값 또는 쿼리 (예: level>=warn AND http.status>=500)">
+            </div>
+
+            <div class="filter-group time-range-group">
+                <select id="time-preset">
+                    <option value="">전체 기간</option>
+                    <option value="now-5m">최근 5분</option>
+                    <option value="now-15m">최근 15분</option>
+                    <option value="now-1h">최근 1시간</option>
+                    <option value="now-24h">최근 24시간</option>
+                    <option value="custom">직접 입력</option>
+                </select>
+                <input type="text" id="time-start" placeholder="시작 (예: now-15m)">
+                <input type="text" id="time-end" placeholder="종료 (예: 2025-07-31T12:00:00Z)">
+            </div>
+
+            <div class="filter-group">
+                <span id="query-error" class="query-error"></span>
             </div>
 
             <div class="filter-group">
@@ -100,9 +120,55 @@ const HTML_INDEX: &str = r#"<!DOCTYPE html>
             </div>
         </div>
 
+        <!-- Trace Panel -->
+        <div id="trace-panel" class="config-panel trace-panel" style="display: none;">
+            <div class="config-header">
+                <h3 id="trace-title">요청 로그</h3>
+                <button id="close-trace-btn" class="btn-close">&times;</button>
+            </div>
+            <div id="trace-content" class="config-content trace-content"></div>
+        </div>
+
+        <div id="row-menu" class="row-menu" style="display: none;"></div>
+
+        <div id="schema-notice" class="schema-notice" style="display: none;"></div>
+
         <div class="log-container">
+            <aside id="facets-panel" class="facets-panel" style="display: none;">
+                <div class="facets-header">
+                    <input type="text" id="facet-fields" value="level,logger" placeholder="필드 (예: level,logger,user.id)">
+                </div>
+                <div id="facets-list" class="facets-list"></div>
+            </aside>
+
+            <aside id="problems-panel" class="facets-panel" style="display: none;">
+                <div class="facets-header">
+                    <select id="problems-sort">
+                        <option value="count">발생 횟수순</option>
+                        <option value="last_seen">최근 발생순</option>
+                        <option value="first_seen">최초 발생순</option>
+                    </select>
+                </div>
+                <div id="problems-list" class="facets-list"></div>
+            </aside>
+
+            <aside id="patterns-panel" class="facets-panel" style="display: none;">
+                <div class="facets-header">
+                    <select id="patterns-sort">
+                        <option value="count">발생 횟수순</option>
+                        <option value="recent">최근 발생순</option>
+                        <option value="new">새 패턴순</option>
+                    </select>
+                    <div id="patterns-summary" class="problem-seen"></div>
+                </div>
+                <div id="patterns-list" class="facets-list"></div>
+            </aside>
 
             <div class="log-table-container" id="log-table-container">
+                <div class="histogram-container">
+                    <canvas id="histogram-canvas" class="histogram-canvas"></canvas>
+                    <div id="histogram-info" class="histogram-info"></div>
+                </div>
                 <div class="log-table-header">
                     <div class="log-header-row" id="log-header-row">
                         <!-- 동적 컬럼 헤더들이 여기에 렌더링됩니다 -->
@@ -386,6 +452,25 @@ body {
     transition: background-color 0.2s, color 0.2s, border-color 0.2s;
 }
 
+.filter-group #filter-value {
+    min-width: 420px;
+}
+
+.time-range-group {
+    flex-direction: row;
+    gap: 0.5rem;
+}
+
+.time-range-group input {
+    min-width: 180px;
+}
+
+.query-error {
+    color: var(--level-error);
+    font-size: 0.8rem;
+    align-self: center;
+}
+
 .filter-group input:focus,
 .filter-group select:focus {
     outline: none;
@@ -396,8 +481,236 @@ body {
 .log-container {
     flex: 1;
     display: flex;
+    flex-direction: row;
+    overflow: hidden;
+}
+
+/* Log volume histogram */
+.trace-panel {
+    width: 640px;
+}
+
+.trace-content {
+    overflow-y: auto;
+    font-size: 0.8rem;
+}
+
+.trace-summary {
+    margin-bottom: 0.75rem;
+    color: var(--text-secondary);
+}
+
+.trace-span {
+    margin: 0.25rem 0 0.25rem 0.75rem;
+    padding-left: 0.5rem;
+    border-left: 2px solid var(--border-primary);
+}
+
+.trace-span-header {
+    font-weight: 600;
+    color: var(--text-accent);
+}
+
+.trace-entry {
+    display: flex;
+    gap: 0.5rem;
+    padding: 0.15rem 0;
+    color: var(--text-primary);
+}
+
+.trace-entry-anchor {
+    background-color: var(--bg-row-hover);
+    font-weight: 600;
+}
+
+.trace-entry-time {
+    flex-shrink: 0;
+    width: 5rem;
+    text-align: right;
+    color: var(--text-secondary);
+}
+
+.trace-entry-level {
+    flex-shrink: 0;
+    width: 3.5rem;
+    font-weight: 600;
+}
+
+.row-menu {
+    position: fixed;
+    z-index: 1100;
+    min-width: 200px;
+    padding: 0.25rem 0;
+    background-color: var(--bg-panel);
+    border: 1px solid var(--border-primary);
+    border-radius: 4px;
+    box-shadow: 0 2px 8px rgba(0, 0, 0, 0.3);
+}
+
+.row-menu-item {
+    padding: 0.4rem 0.75rem;
+    font-size: 0.85rem;
+    color: var(--text-primary);
+    cursor: pointer;
+}
+
+.row-menu-item:hover {
+    background-color: var(--bg-row-hover);
+}
+
+.schema-notice {
+    display: flex;
+    flex-wrap: wrap;
+    align-items: center;
+    gap: 0.5rem;
+    padding: 0.5rem 1rem;
+    background-color: var(--bg-tertiary);
+    border-bottom: 1px solid var(--border-primary);
+    color: var(--text-primary);
+    font-size: 0.875rem;
+}
+
+.schema-notice-item {
+    display: inline-flex;
+    align-items: center;
+    gap: 0.25rem;
+}
+
+.schema-notice-item code {
+    font-family: monospace;
+    color: var(--text-secondary);
+}
+
+.schema-notice .btn {
+    padding: 0.125rem 0.5rem;
+    font-size: 0.75rem;
+}
+
+.histogram-container {
+    flex-shrink: 0;
+    position: relative;
+    height: 90px;
+    padding: 0.5rem 1rem 0.25rem;
+    background-color: var(--bg-secondary);
+    border-bottom: 1px solid var(--border-primary);
+}
+
+.histogram-canvas {
+    width: 100%;
+    height: 100%;
+    cursor: crosshair;
+    display: block;
+}
+
+.histogram-info {
+    position: absolute;
+    top: 0.25rem;
+    right: 1rem;
+    font-size: 0.75rem;
+    color: var(--text-secondary);
+    pointer-events: none;
+}
+
+/* Facets sidebar */
+.facets-panel {
+    width: 280px;
+    flex-shrink: 0;
+    display: flex;
     flex-direction: column;
+    background-color: var(--bg-secondary);
+    border-right: 1px solid var(--border-primary);
+}
+
+.facets-header {
+    padding: 0.75rem;
+    border-bottom: 1px solid var(--border-primary);
+}
+
+.facets-header input,
+.facets-header select {
+    width: 100%;
+    padding: 0.4rem;
+    border: 1px solid var(--border-primary);
+    border-radius: 4px;
+    background-color: var(--bg-input);
+    color: var(--text-primary);
+}
+
+.facets-list {
+    flex: 1;
+    overflow-y: auto;
+    padding: 0.5rem 0.75rem;
+}
+
+.facet {
+    margin-bottom: 1rem;
+}
+
+.facet-title {
+    display: flex;
+    justify-content: space-between;
+    color: var(--text-accent);
+    font-size: 0.8rem;
+    font-weight: 600;
+    margin-bottom: 0.25rem;
+}
+
+.facet-distinct {
+    color: var(--text-secondary);
+    font-weight: normal;
+}
+
+.facet-value {
+    display: flex;
+    justify-content: space-between;
+    gap: 0.5rem;
+    padding: 0.15rem 0.25rem;
+    font-size: 0.8rem;
+    border-radius: 3px;
+    cursor: pointer;
+}
+
+.facet-value:hover {
+    background-color: var(--bg-row-hover);
+}
+
+.facet-value-name {
     overflow: hidden;
+    text-overflow: ellipsis;
+    white-space: nowrap;
+}
+
+.facet-value-count {
+    color: var(--text-secondary);
+    flex-shrink: 0;
+}
+
+.problem {
+    padding: 0.4rem 0.25rem;
+    border-bottom: 1px solid var(--border-primary);
+    font-size: 0.8rem;
+    cursor: pointer;
+}
+
+.problem:hover {
+    background-color: var(--bg-row-hover);
+}
+
+.problem-header {
+    display: flex;
+    justify-content: space-between;
+    font-weight: 600;
+}
+
+.problem-template {
+    margin: 0.2rem 0;
+    color: var(--text-primary);
+    word-break: break-word;
+}
+
+.problem-seen {
+    color: var(--text-secondary);
+    font-size: 0.75rem;
 }
 
 .log-table-container {
@@ -407,6 +720,32 @@ body {
     overflow: hidden;
 }
 
+.repeat-badge {
+    display: inline-block;
+    margin-left: 0.35rem;
+    padding: 0 0.35rem;
+    border-radius: 8px;
+    background-color: var(--bg-tertiary);
+    color: var(--text-accent);
+    font-size: 0.75rem;
+    font-weight: 600;
+    cursor: pointer;
+}
+
+.virtual-log-row.repeat-sample-row {
+    opacity: 0.7;
+    font-style: italic;
+}
+
+.virtual-log-row.gap-row {
+    justify-content: center;
+    align-items: center;
+    color: var(--text-secondary);
+    font-style: italic;
+    border-top: 1px dashed var(--border-primary);
+    border-bottom: 1px dashed var(--border-primary);
+}
+
 .log-table-header {
     flex-shrink: 0;
     background-color: var(--bg-header);
@@ -779,6 +1118,12 @@ const JS_APP: &str = r#"class JsonWebLogApp {
             column: '',
             value: ''
         };
+        // Filter evaluated by the server: query expression (when no column is selected) and time range
+        this.serverFilter = {
+            q: '',
+            start: '',
+            end: ''
+        };
         this.ws = null;
         this.reconnectAttempts = 0;
         this.maxReconnectAttempts = 5;
@@ -786,8 +1131,38 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         this.currentTheme = 'dark'; // Default theme
         this.autoScrollEnabled = true; // Default auto-scroll to true
         
+        // Log volume histogram; dragging across it selects a time range
+        this.histogram = {
+            data: null,
+            dragStart: null,
+            dragEnd: null
+        };
+
+        // Facets sidebar, refreshed periodically while open
+        this.facets = {
+            open: false,
+            timer: null
+        };
+
+        // Problems sidebar listing warning/error groups by fingerprint
+        this.problems = {
+            open: false,
+            timer: null
+        };
+
+        // Patterns sidebar listing learned message templates
+        this.patterns = {
+            open: false,
+            timer: null
+        };
+
         // Column configuration
         this.columns = []; // Simple list of column configs from server
+        this.fieldCatalog = {}; // Field path -> types, coverage and examples from /api/schema
+        this.schemaChanges = new Map(); // Field path -> pending schema change notice
+        this.expandedRepeats = new Set(); // Sequence IDs of folded entries shown expanded
+        this.correlation = null; // Correlation keys from /api/correlation
+        this.rowMenuActions = []; // Actions of the open row context menu
         this.isConfigPanelOpen = false;
         this.resizing = null; // For column resizing
         
@@ -807,8 +1182,10 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         this.initializeElements();
         this.setupEventListeners();
         this.setupVirtualScrolling();
+        this.setupHistogram();
         this.connectWebSocket();
         this.initializeSettings();
+        this.loadCorrelation();
         this.throttledApplyFilters = this.throttle(this.applyFilters.bind(this), 50, { 'maxWait': 500 });
     }
 
@@ -816,9 +1193,34 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         this.elements = {
             filterColumn: document.getElementById('filter-column'),
             filterValue: document.getElementById('filter-value'),
+            queryError: document.getElementById('query-error'),
+            timePreset: document.getElementById('time-preset'),
+            timeStart: document.getElementById('time-start'),
+            timeEnd: document.getElementById('time-end'),
             clearFiltersBtn: document.getElementById('clear-filters-btn'),
             clearBtn: document.getElementById('clear-btn'),
             columnsBtn: document.getElementById('columns-btn'),
+            facetsBtn: document.getElementById('facets-btn'),
+            problemsBtn: document.getElementById('problems-btn'),
+            problemsPanel: document.getElementById('problems-panel'),
+            problemsSort: document.getElementById('problems-sort'),
+            problemsList: document.getElementById('problems-list'),
+            patternsBtn: document.getElementById('patterns-btn'),
+            patternsPanel: document.getElementById('patterns-panel'),
+            patternsSort: document.getElementById('patterns-sort'),
+            patternsSummary: document.getElementById('patterns-summary'),
+            patternsList: document.getElementById('patterns-list'),
+            facetsPanel: document.getElementById('facets-panel'),
+            facetFields: document.getElementById('facet-fields'),
+            facetsList: document.getElementById('facets-list'),
+            histogramCanvas: document.getElementById('histogram-canvas'),
+            histogramInfo: document.getElementById('histogram-info'),
+            schemaNotice: document.getElementById('schema-notice'),
+            tracePanel: document.getElementById('trace-panel'),
+            traceTitle: document.getElementById('trace-title'),
+            traceContent: document.getElementById('trace-content'),
+            closeTraceBtn: document.getElementById('close-trace-btn'),
+            rowMenu: document.getElementById('row-menu'),
             themeSelector: document.getElementById('theme-selector'),
             autoScrollCheckbox: document.getElementById('auto-scroll-checkbox'),
             columnConfigPanel: document.getElementById('column-config-panel'),
@@ -839,11 +1241,38 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         // Filter event listeners
         this.elements.filterColumn.addEventListener('change', () => this.updateFilter('column', this.elements.filterColumn.value));
         this.elements.filterValue.addEventListener('input', this.debounce(() => this.updateFilter('value', this.elements.filterValue.value), 300));
+        this.elements.timePreset.addEventListener('change', () => this.applyTimePreset(this.elements.timePreset.value));
+        this.elements.timeStart.addEventListener('change', () => this.updateTimeRange());
+        this.elements.timeEnd.addEventListener('change', () => this.updateTimeRange());
         
         // Button event listeners
         this.elements.clearFiltersBtn.addEventListener('click', () => this.clearFilters());
         this.elements.clearBtn.addEventListener('click', () => this.clearLogs());
         this.elements.columnsBtn.addEventListener('click', () => this.showColumnConfig());
+        this.elements.facetsBtn.addEventListener('click', () => this.toggleFacets());
+        this.elements.facetFields.addEventListener('change', () => this.refreshFacets());
+        this.elements.facetsList.addEventListener('click', (e) => {
+            const item = e.target.closest('.facet-value');
+            if (item) {
+                this.applyFacet(item.dataset.field, item.dataset.value);
+            }
+        });
+        this.elements.problemsBtn.addEventListener('click', () => this.toggleProblems());
+        this.elements.problemsSort.addEventListener('change', () => this.refreshProblems());
+        this.elements.problemsList.addEventListener('click', (e) => {
+            const item = e.target.closest('.problem');
+            if (item) {
+                this.showProblem(item.dataset.fingerprint);
+            }
+        });
+        this.elements.patternsBtn.addEventListener('click', () => this.togglePatterns());
+        this.elements.patternsSort.addEventListener('change', () => this.refreshPatterns());
+        this.elements.patternsList.addEventListener('click', (e) => {
+            const item = e.target.closest('.problem');
+            if (item) {
+                this.showPattern(item.dataset.patternId);
+            }
+        });
         this.elements.themeSelector.addEventListener('change', (e) => this.applyTheme(e.target.value));
         this.elements.autoScrollCheckbox.addEventListener('change', (e) => this.toggleAutoScroll(e.target.checked));
         
@@ -852,11 +1281,30 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         this.elements.applyColumnsBtn.addEventListener('click', () => this.applyColumnConfiguration());
         this.elements.showAllBtn.addEventListener('click', () => this.showAllColumns());
 
+        // Row actions
+        this.elements.virtualScrollViewport.addEventListener('contextmenu', (e) => this.showRowMenu(e));
+        this.elements.rowMenu.addEventListener('click', (e) => {
+            const item = e.target.closest('.row-menu-item');
+            if (item) {
+                this.rowMenuActions[item.dataset.action]();
+            }
+            this.hideRowMenu();
+        });
+        document.addEventListener('click', (e) => {
+            if (!this.elements.rowMenu.contains(e.target)) {
+                this.hideRowMenu();
+            }
+        });
+        this.elements.closeTraceBtn.addEventListener('click', () => this.hideTrace());
+
         // Global keydown listener for ESC key
         document.addEventListener('keydown', (e) => {
-            if (e.key === 'Escape' && this.isConfigPanelOpen) {
+            if (e.key !== 'Escape') return;
+            this.hideRowMenu();
+            if (this.isConfigPanelOpen) {
                 this.hideColumnConfig();
             }
+            this.hideTrace();
         });
     }
 
@@ -968,9 +1416,17 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         
     }
 
-    connectWebSocket() {
+    connectWebSocket(resume = false) {
         const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:';
-        const wsUrl = `${protocol}//${window.location.host}/ws`;
+        const params = this.serverFilterParams();
+        // After a dropped connection, continue from the last entry instead of replaying the buffer
+        const lastSeq = resume ? this.lastSeq() : 0;
+        if (lastSeq) {
+            params.set('resume_after', lastSeq);
+        }
+        // Batched frames keep a busy stream from sending one message per entry
+        params.set('batch', 'true');
+        const wsUrl = `${protocol}//${window.location.host}/ws?${params}`;
         
         this.ws = new WebSocket(wsUrl);
         
@@ -981,8 +1437,15 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         
         this.ws.onmessage = (event) => {
             try {
-                const logEntry = JSON.parse(event.data);
-                this.addLogEntry(logEntry);
+                const message = JSON.parse(event.data);
+                // Entries arrive in array frames; control messages are objects with a type
+                if (Array.isArray(message)) {
+                    this.addLogEntries(message);
+                    return;
+                }
+                if (!this.handleControlMessage(message)) {
+                    this.addLogEntries([message]);
+                }
             } catch (error) {
                 console.error('Failed to parse log entry:', error);
             }
@@ -998,17 +1461,63 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         };
     }
 
+    handleControlMessage(message) {
+        switch (message.type) {
+            case 'subscribed':
+                if (message.resumed) return true;
+                // A fresh backfill for the new subscription follows
+                this.logs = [];
+                this.expandedRepeats.clear();
+                this.applyFilters();
+                return true;
+            case 'backfill_complete':
+                return true;
+            case 'lagged':
+                // The server resends the skipped entries from its buffer
+                console.warn(`WebSocket fell behind by ${message.skipped} entries; resynchronizing`);
+                return true;
+            case 'gap':
+                this.logs.push({ gap: { first_seq: message.first_seq, last_seq: message.last_seq } });
+                this.throttledApplyFilters();
+                return true;
+            case 'error':
+                this.elements.queryError.textContent = message.message;
+                return true;
+            case 'schema_change':
+                if (!Array.isArray(message.changes)) return false;
+                this.handleSchemaChanges(message.changes);
+                return true;
+            default:
+                return false;
+        }
+    }
+
     scheduleReconnect() {
         if (this.reconnectAttempts < this.maxReconnectAttempts) {
             this.reconnectAttempts++;
             const delay = Math.min(1000 * Math.pow(2, this.reconnectAttempts), 30000);
-            setTimeout(() => this.connectWebSocket(), delay);
+            setTimeout(() => this.connectWebSocket(true), delay);
         }
     }
 
-    addLogEntry(logEntry) {
-        this.logs.push(logEntry);
-        
+    lastSeq() {
+        for (let i = this.logs.length - 1; i >= 0; i--) {
+            if (this.logs[i].seq) return this.logs[i].seq;
+        }
+        return 0;
+    }
+
+    addLogEntries(entries) {
+        entries.forEach(logEntry => {
+            // Folded repeats are re-sent with the same sequence ID; replace the earlier copy
+            const last = this.logs[this.logs.length - 1];
+            if (last && logEntry.seq && last.seq === logEntry.seq) {
+                this.logs[this.logs.length - 1] = logEntry;
+            } else {
+                this.logs.push(logEntry);
+            }
+        });
+
         // Keep only last 100,000 entries to prevent memory issues
         if (this.logs.length > 100000) {
             this.logs = this.logs.slice(-100000);
@@ -1019,13 +1528,502 @@ const JS_APP: &str = r#"class JsonWebLogApp {
 
     updateFilter(filterType, value) {
         this.filters[filterType] = value;
+
+        // Without a column the search box holds a query expression evaluated by the server
+        const query = this.filters.column ? '' : this.filters.value.trim();
+        if (query !== this.serverFilter.q) {
+            this.setServerFilter({ q: query });
+            return;
+        }
+
+        this.applyFilters();
+    }
+
+    applyTimePreset(preset) {
+        if (preset === 'custom') {
+            this.elements.timeStart.focus();
+            return;
+        }
+        this.elements.timeStart.value = preset;
+        this.elements.timeEnd.value = '';
+        this.updateTimeRange();
+    }
+
+    updateTimeRange() {
+        this.setServerFilter({
+            start: this.elements.timeStart.value.trim(),
+            end: this.elements.timeEnd.value.trim()
+        });
+    }
+
+    serverFilterParams(filter = this.serverFilter) {
+        const params = new URLSearchParams();
+        Object.entries(filter).forEach(([key, value]) => {
+            if (value) params.set(key, value);
+        });
+        return params;
+    }
+
+    async setServerFilter(changes) {
+        const next = { ...this.serverFilter, ...changes };
+        const params = this.serverFilterParams(next);
+
+        if (params.toString()) {
+            // Validate first: a rejected WebSocket upgrade carries no readable error
+            try {
+                const response = await fetch(`/api/logs?limit=0&${params}`);
+                if (!response.ok) {
+                    this.elements.queryError.textContent = await response.text();
+                    return;
+                }
+            } catch (error) {
+                console.error('Failed to validate filter:', error);
+                return;
+            }
+        }
+
+        this.elements.queryError.textContent = '';
+        this.serverFilter = next;
+        this.resubscribe();
+        this.refreshFacets();
+        this.refreshHistogram();
+    }
+
+    setupHistogram() {
+        const canvas = this.elements.histogramCanvas;
+        canvas.addEventListener('mousedown', (e) => {
+            this.histogram.dragStart = this.histogramBucketAt(e);
+            this.histogram.dragEnd = this.histogram.dragStart;
+        });
+        canvas.addEventListener('mousemove', (e) => {
+            const bucket = this.histogramBucketAt(e);
+            if (this.histogram.dragStart !== null) {
+                this.histogram.dragEnd = bucket;
+                this.drawHistogram();
+            }
+            this.showHistogramInfo(bucket);
+        });
+        canvas.addEventListener('mouseleave', () => {
+            this.elements.histogramInfo.textContent = '';
+        });
+        document.addEventListener('mouseup', () => this.finishHistogramSelection());
+        window.addEventListener('resize', this.debounce(() => this.drawHistogram(), 250));
+
+        this.refreshHistogram();
+        setInterval(() => this.refreshHistogram(), 5000);
+    }
+
+    async refreshHistogram() {
+        const params = this.serverFilterParams();
+        params.set('interval', 'auto');
+
+        try {
+            const response = await fetch(`/api/histogram?${params}`);
+            if (!response.ok) return;
+            this.histogram.data = await response.json();
+            this.drawHistogram();
+        } catch (error) {
+            console.error('Failed to load histogram:', error);
+        }
+    }
+
+    histogramBucketAt(e) {
+        const data = this.histogram.data;
+        if (!data || data.buckets.length === 0) return null;
+        const rect = this.elements.histogramCanvas.getBoundingClientRect();
+        const ratio = Math.min(Math.max((e.clientX - rect.left) / rect.width, 0), 0.9999);
+        return Math.floor(ratio * data.buckets.length);
+    }
+
+    showHistogramInfo(index) {
+        const data = this.histogram.data;
+        if (index === null || !data) return;
+        const bucket = data.buckets[index];
+        const levels = Object.entries(bucket.levels)
+            .filter(([, count]) => count > 0)
+            .map(([level, count]) => `${level} ${count}`)
+            .join(' · ');
+        this.elements.histogramInfo.textContent =
+            `${new Date(bucket.start).toLocaleString()} (${data.interval_seconds}s) — ${bucket.total}${levels ? ': ' + levels : ''}`;
+    }
+
+    drawHistogram() {
+        const canvas = this.elements.histogramCanvas;
+        const data = this.histogram.data;
+        const ratio = window.devicePixelRatio || 1;
+        const width = canvas.clientWidth;
+        const height = canvas.clientHeight;
+        canvas.width = width * ratio;
+        canvas.height = height * ratio;
+
+        const ctx = canvas.getContext('2d');
+        ctx.scale(ratio, ratio);
+        ctx.clearRect(0, 0, width, height);
+        if (!data || data.buckets.length === 0) return;
+
+        const style = getComputedStyle(document.documentElement);
+        const levelOrder = ['TRACE', 'DEBUG', 'INFO', 'WARN', 'ERROR', 'FATAL'];
+        const colors = levelOrder.map(level => style.getPropertyValue(`--level-${level.toLowerCase()}`).trim());
+        const maxTotal = Math.max(1, ...data.buckets.map(bucket => bucket.total));
+        const barWidth = width / data.buckets.length;
+
+        data.buckets.forEach((bucket, index) => {
+            let y = height;
+            levelOrder.forEach((level, levelIndex) => {
+                const count = bucket.levels[level] || 0;
+                if (count === 0) return;
+                const barHeight = (count / maxTotal) * height;
+                ctx.fillStyle = colors[levelIndex];
+                ctx.fillRect(index * barWidth, y - barHeight, Math.max(barWidth - 1, 1), barHeight);
+                y -= barHeight;
+            });
+        });
+
+        const { dragStart, dragEnd } = this.histogram;
+        if (dragStart !== null && dragEnd !== null) {
+            const from = Math.min(dragStart, dragEnd);
+            const to = Math.max(dragStart, dragEnd);
+            ctx.fillStyle = style.getPropertyValue('--border-resizer-hover').trim();
+            ctx.fillRect(from * barWidth, 0, (to - from + 1) * barWidth, height);
+        }
+    }
+
+    finishHistogramSelection() {
+        const { dragStart, dragEnd, data } = this.histogram;
+        this.histogram.dragStart = null;
+        this.histogram.dragEnd = null;
+        if (dragStart === null || dragEnd === null || !data) return;
+
+        const from = data.buckets[Math.min(dragStart, dragEnd)];
+        const to = data.buckets[Math.max(dragStart, dragEnd)];
+        const end = new Date(new Date(to.start).getTime() + data.interval_seconds * 1000);
+
+        this.elements.timePreset.value = 'custom';
+        this.elements.timeStart.value = new Date(from.start).toISOString();
+        this.elements.timeEnd.value = end.toISOString();
+        this.updateTimeRange();
+    }
+
+    toggleFacets() {
+        this.facets.open = !this.facets.open;
+        this.elements.facetsPanel.style.display = this.facets.open ? 'flex' : 'none';
+        clearInterval(this.facets.timer);
+        if (this.facets.open) {
+            this.refreshFacets();
+            this.facets.timer = setInterval(() => this.refreshFacets(), 5000);
+        }
+        this.handleResize();
+        this.drawHistogram();
+    }
+
+    async refreshFacets() {
+        if (!this.facets.open) return;
+
+        const params = this.serverFilterParams();
+        params.set('group_by', this.elements.facetFields.value);
+        params.set('top', '10');
+
+        try {
+            const response = await fetch(`/api/aggregate?${params}`);
+            if (!response.ok) {
+                this.elements.facetsList.innerHTML = `<div class="loading">${this.escapeHtml(await response.text())}</div>`;
+                return;
+            }
+            this.renderFacets(await response.json());
+        } catch (error) {
+            console.error('Failed to load facets:', error);
+        }
+    }
+
+    renderFacets(result) {
+        const html = result.facets.map(facet => {
+            const values = facet.top.map(item => `
+                <div class="facet-value" data-field="${this.escapeHtml(facet.field)}" data-value="${this.escapeHtml(item.value)}">
+                    <span class="facet-value-name">${this.escapeHtml(item.value)}</span>
+                    <span class="facet-value-count">${item.count}</span>
+                </div>
+            `).join('');
+            return `
+                <div class="facet">
+                    <div class="facet-title">
+                        <span>${this.escapeHtml(facet.field)}</span>
+                        <span class="facet-distinct">${facet.approximate ? '~' : ''}${facet.distinct_count}개 값</span>
+                    </div>
+                    ${values || '<div class="facet-value-count">값 없음</div>'}
+                </div>
+            `;
+        }).join('');
+        this.elements.facetsList.innerHTML = html;
+    }
+
+    applyFacet(field, value) {
+        // Narrow the current query with the clicked value
+        const term = `${field}:"${value.replace(/["\\]/g, '\\$&')}"`;
+        const query = this.serverFilter.q ? `(${this.serverFilter.q}) AND ${term}` : term;
+        this.applyQuery(query);
+    }
+
+    applyQuery(query) {
+        // Show the query in the search box and filter on the server
+        this.filters.column = '';
+        this.filters.value = query;
+        this.elements.filterColumn.value = '';
+        this.elements.filterValue.value = query;
+        this.setServerFilter({ q: query });
+    }
+
+    async loadCorrelation() {
+        try {
+            const response = await fetch('/api/correlation');
+            if (response.ok) {
+                this.correlation = await response.json();
+            }
+        } catch (error) {
+            console.error('Failed to load correlation keys:', error);
+        }
+    }
+
+    rowActions(log) {
+        const actions = [];
+        if (log.seq) {
+            actions.push({ label: '앞뒤 로그 보기', run: () => this.showContext(log.seq) });
+            actions.push({ label: '앞뒤 로그 보기 (같은 로거)', run: () => this.showContext(log.seq, 'logger') });
+            if (log.source) {
+                actions.push({ label: '앞뒤 로그 보기 (같은 소스)', run: () => this.showContext(log.seq, 'source') });
+            }
+        }
+        (this.correlation ? this.correlation.keys : []).forEach(key => {
+            const value = this.getFieldValue(log, key);
+            if (value === '' || value === null || typeof value === 'object') return;
+            actions.push({
+                label: `이 요청의 모든 로그 보기 (${key}=${value})`,
+                run: () => this.showTrace(String(value), key)
+            });
+        });
+        return actions;
+    }
+
+    showRowMenu(e) {
+        const row = e.target.closest('.virtual-log-row');
+        const log = row ? this.filteredLogs[row.dataset.index] : null;
+        const actions = log && !log.gap ? this.rowActions(log) : [];
+        if (actions.length === 0) return; // Keep the browser menu
+
+        e.preventDefault();
+        this.rowMenuActions = actions.map(action => action.run);
+        this.elements.rowMenu.innerHTML = actions.map((action, index) =>
+            `<div class="row-menu-item" data-action="${index}">${this.escapeHtml(action.label)}</div>`
+        ).join('');
+        this.elements.rowMenu.style.left = `${e.clientX}px`;
+        this.elements.rowMenu.style.top = `${e.clientY}px`;
+        this.elements.rowMenu.style.display = 'block';
+    }
+
+    hideRowMenu() {
+        this.elements.rowMenu.style.display = 'none';
+    }
+
+    async showTrace(id, key) {
+        this.elements.traceTitle.textContent = `${key} = ${id}`;
+        this.elements.traceContent.innerHTML = '<div class="loading">불러오는 중...</div>';
+        this.elements.tracePanel.style.display = 'flex';
+
+        try {
+            const response = await fetch(`/api/trace/${encodeURIComponent(id)}?key=${encodeURIComponent(key)}`);
+            if (!response.ok) {
+                this.elements.traceContent.innerHTML = `<div class="loading">${this.escapeHtml(await response.text())}</div>`;
+                return;
+            }
+            this.renderTrace(await response.json());
+        } catch (error) {
+            console.error('Failed to load trace:', error);
+        }
+    }
+
+    hideTrace() {
+        this.elements.tracePanel.style.display = 'none';
+    }
+
+    renderPanelEntry(entry, baseTime, highlight = false) {
+        const offset = new Date(entry.timestamp).getTime() - baseTime;
+        return `
+            <div class="trace-entry${highlight ? ' trace-entry-anchor' : ''}">
+                <span class="trace-entry-time">${offset >= 0 ? '+' : ''}${offset}ms</span>
+                <span class="trace-entry-level" style="color: var(--level-${entry.level.toLowerCase()});">${entry.level.toUpperCase()}</span>
+                <span>${this.escapeHtml(entry.logger)}: ${this.escapeHtml(entry.message)}</span>
+            </div>
+        `;
+    }
+
+    async showContext(seq, same = '') {
+        this.elements.traceTitle.textContent = `컨텍스트 #${seq}${same ? ` (같은 ${same === 'logger' ? '로거' : '소스'})` : ''}`;
+        this.elements.traceContent.innerHTML = '<div class="loading">불러오는 중...</div>';
+        this.elements.tracePanel.style.display = 'flex';
+
+        const params = new URLSearchParams({ before: '50', after: '50' });
+        if (same) params.set('same', same);
+        try {
+            const response = await fetch(`/api/logs/${seq}/context?${params}`);
+            if (!response.ok) {
+                this.elements.traceContent.innerHTML = `<div class="loading">${this.escapeHtml(await response.text())}</div>`;
+                return;
+            }
+            const context = await response.json();
+            const baseTime = new Date(context.entry.timestamp).getTime();
+            this.elements.traceContent.innerHTML = `
+                ${context.before.map(entry => this.renderPanelEntry(entry, baseTime)).join('')}
+                ${this.renderPanelEntry(context.entry, baseTime, true)}
+                ${context.after.map(entry => this.renderPanelEntry(entry, baseTime)).join('')}
+            `;
+            const anchor = this.elements.traceContent.querySelector('.trace-entry-anchor');
+            if (anchor) anchor.scrollIntoView({ block: 'center' });
+        } catch (error) {
+            console.error('Failed to load context:', error);
+        }
+    }
+
+    renderTrace(trace) {
+        const start = new Date(trace.start).getTime();
+        const renderEntry = (entry) => this.renderPanelEntry(entry, start);
+        const renderSpan = (span) => `
+            <div class="trace-span">
+                <div class="trace-span-header">span ${this.escapeHtml(span.span_id)}
+                    (${new Date(span.end).getTime() - new Date(span.start).getTime()}ms)</div>
+                ${span.entries.map(renderEntry).join('')}
+                ${span.children.map(renderSpan).join('')}
+            </div>
+        `;
+
+        this.elements.traceContent.innerHTML = `
+            <div class="trace-summary">로그 ${trace.count}개 · ${trace.duration_ms}ms · ${new Date(trace.start).toLocaleString()}</div>
+            ${trace.entries.map(renderEntry).join('')}
+            ${trace.spans.map(renderSpan).join('')}
+        `;
+    }
+
+    toggleProblems() {
+        this.problems.open = !this.problems.open;
+        this.elements.problemsPanel.style.display = this.problems.open ? 'flex' : 'none';
+        clearInterval(this.problems.timer);
+        if (this.problems.open) {
+            this.refreshProblems();
+            this.problems.timer = setInterval(() => this.refreshProblems(), 5000);
+        }
+        this.handleResize();
+        this.drawHistogram();
+    }
+
+    async refreshProblems() {
+        if (!this.problems.open) return;
+
+        try {
+            const response = await fetch(`/api/groups?sort=${this.elements.problemsSort.value}&limit=200`);
+            if (!response.ok) return;
+            this.renderProblems(await response.json());
+        } catch (error) {
+            console.error('Failed to load problems:', error);
+        }
+    }
+
+    renderProblems(result) {
+        if (result.groups.length === 0) {
+            this.elements.problemsList.innerHTML = '<div class="loading">경고나 오류가 없습니다.</div>';
+            return;
+        }
+
+        const html = result.groups.map(group => {
+            const level = group.level.toLowerCase();
+            return `
+                <div class="problem" data-fingerprint="${this.escapeHtml(group.fingerprint)}" title="${this.escapeHtml(group.example)}">
+                    <div class="problem-header">
+                        <span style="color: var(--level-${level});">${group.level.toUpperCase()}</span>
+                        <span>${group.count.toLocaleString()}회</span>
+                    </div>
+                    <div class="problem-template">${this.escapeHtml(group.template)}</div>
+                    <div class="problem-seen">
+                        ${new Date(group.first_seen).toLocaleTimeString()} ~ ${new Date(group.last_seen).toLocaleTimeString()}
+                    </div>
+                </div>
+            `;
+        }).join('');
+        this.elements.problemsList.innerHTML = html;
+    }
+
+    showProblem(fingerprint) {
+        // Show only the members of the clicked group
+        this.applyQuery(`fingerprint:"${fingerprint}"`);
+    }
+
+    togglePatterns() {
+        this.patterns.open = !this.patterns.open;
+        this.elements.patternsPanel.style.display = this.patterns.open ? 'flex' : 'none';
+        clearInterval(this.patterns.timer);
+        if (this.patterns.open) {
+            this.refreshPatterns();
+            this.patterns.timer = setInterval(() => this.refreshPatterns(), 5000);
+        }
+        this.handleResize();
+        this.drawHistogram();
+    }
+
+    async refreshPatterns() {
+        if (!this.patterns.open) return;
+
+        try {
+            const response = await fetch(`/api/patterns?sort=${this.elements.patternsSort.value}&limit=200&new_within=5m`);
+            if (!response.ok) return;
+            this.renderPatterns(await response.json());
+        } catch (error) {
+            console.error('Failed to load patterns:', error);
+        }
+    }
+
+    renderPatterns(result) {
+        this.elements.patternsSummary.textContent =
+            `패턴 ${result.total_patterns.toLocaleString()}개 · 최근 5분간 새 패턴 ${result.new_patterns}개`;
+
+        const html = result.patterns.map(pattern => `
+            <div class="problem" data-pattern-id="${pattern.id}">
+                <div class="problem-header">
+                    <span>#${pattern.id}</span>
+                    <span>${pattern.count.toLocaleString()}회</span>
+                </div>
+                <div class="problem-template">${this.escapeHtml(pattern.template)}</div>
+                <div class="problem-seen">
+                    ${new Date(pattern.first_seen).toLocaleTimeString()} ~ ${new Date(pattern.last_seen).toLocaleTimeString()}
+                </div>
+            </div>
+        `).join('');
+        this.elements.patternsList.innerHTML = html || '<div class="loading">학습된 패턴이 없습니다.</div>';
+    }
+
+    showPattern(patternId) {
+        this.applyQuery(`pattern_id:${patternId}`);
+    }
+
+    resubscribe() {
+        // Change the filter on the open socket; the server clears and backfills
+        if (this.ws && this.ws.readyState === WebSocket.OPEN) {
+            const params = Object.fromEntries(this.serverFilterParams());
+            this.ws.send(JSON.stringify({ type: 'subscribe', ...params }));
+            return;
+        }
+
+        if (this.ws) {
+            this.ws.onclose = null; // Don't trigger the automatic reconnect
+            this.ws.close();
+        }
+        this.logs = [];
+        this.filteredLogs = [];
+        this.reconnectAttempts = 0;
+        this.connectWebSocket();
         this.applyFilters();
     }
 
     applyFilters() {
         // If no filter is applied, just use the raw logs
         if (!this.filters.column && !this.filters.value) {
-            this.filteredLogs = this.logs.slice(); // Create a shallow copy
+            this.filteredLogs = this.expandRepeats(this.logs.slice()); // Create a shallow copy
             this.updateDisplay();
             return;
         }
@@ -1044,10 +2042,41 @@ const JS_APP: &str = r#"class JsonWebLogApp {
             
             return true;
         });
+        this.filteredLogs = this.expandRepeats(this.filteredLogs);
         
         this.updateDisplay();
     }
 
+    expandRepeats(logs) {
+        if (this.expandedRepeats.size === 0) return logs;
+
+        const expanded = [];
+        logs.forEach(log => {
+            expanded.push(log);
+            if (!log.repeat || !this.expandedRepeats.has(log.seq)) return;
+
+            // Folded entries only keep line, time and message; other fields come from the first one
+            log.repeat.samples.forEach(sample => {
+                const rawFields = { ...log.raw_fields };
+                Object.keys(rawFields).forEach(key => {
+                    if (rawFields[key] === log.message) rawFields[key] = sample.message;
+                });
+                expanded.push({ ...log, line: sample.line, timestamp: sample.timestamp, message: sample.message,
+                    raw_fields: rawFields, repeat: null, repeatSample: true });
+            });
+        });
+        return expanded;
+    }
+
+    toggleRepeat(seq) {
+        if (this.expandedRepeats.has(seq)) {
+            this.expandedRepeats.delete(seq);
+        } else {
+            this.expandedRepeats.add(seq);
+        }
+        this.applyFilters();
+    }
+
     updateDisplay() {
         // Check if user was at bottom before update
         const wasAtBottom = this.isAtBottom();
@@ -1115,11 +2144,21 @@ const JS_APP: &str = r#"class JsonWebLogApp {
     createVirtualLogRow(log, virtualIndex) {
         const row = document.createElement('div');
         row.className = 'virtual-log-row';
+        row.dataset.index = virtualIndex;
         
         // Add level-specific class for background color
         if (log.level) {
             row.classList.add(`row-level-${log.level.toLowerCase()}`);
         }
+        if (log.repeatSample) {
+            row.classList.add('repeat-sample-row');
+        }
+        if (log.gap) {
+            const missed = log.gap.last_seq - log.gap.first_seq + 1;
+            row.classList.add('gap-row');
+            row.textContent = `연결이 끊긴 동안 로그 ${missed.toLocaleString()}개가 버퍼에서 밀려나 표시되지 않았습니다 (#${log.gap.first_seq}–#${log.gap.last_seq})`;
+            return row;
+        }
         
         // Initialize columns from first log if not already done
         if (this.columns.length === 0 && log.raw_fields) {
@@ -1136,7 +2175,12 @@ const JS_APP: &str = r#"class JsonWebLogApp {
                 .filter(col => col.visible);
             const html = visibleColumns.map(column => {
                 const value = this.getFieldValue(log, column.field_name);
-                const formattedValue = this.formatValue(value);
+                let formattedValue = this.formatValue(value);
+                if (column.field_name === '#' && log.repeat) {
+                    const repeat = log.repeat;
+                    const title = `${new Date(repeat.first_timestamp).toLocaleString()} ~ ${new Date(repeat.last_timestamp).toLocaleString()}`;
+                    formattedValue += `<span class="repeat-badge" title="${title}" onclick="app.toggleRepeat(${log.seq})">×${repeat.count.toLocaleString()}</span>`;
+                }
                 
                 return `<div class="dynamic-column" style="width: ${column.width}px; flex: 0 0 ${column.width}px;">${formattedValue}</div>`;
             }).join('');
@@ -1183,7 +2227,16 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         if (log.raw_fields && log.raw_fields.hasOwnProperty(fieldName)) {
             return log.raw_fields[fieldName];
         }
-        return '';
+
+        // Resolve nested paths such as "http.status" from the field catalog
+        let value = log.raw_fields;
+        for (const part of fieldName.split('.')) {
+            if (value === null || typeof value !== 'object' || !value.hasOwnProperty(part)) {
+                return '';
+            }
+            value = value[part];
+        }
+        return value;
     }
 
     formatValue(value) {
@@ -1212,7 +2265,15 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         
         this.elements.filterColumn.value = '';
         this.elements.filterValue.value = '';
+        this.elements.queryError.textContent = '';
+        this.elements.timePreset.value = '';
+        this.elements.timeStart.value = '';
+        this.elements.timeEnd.value = '';
         
+        if (this.serverFilterParams().toString()) {
+            this.setServerFilter({ q: '', start: '', end: '' });
+            return;
+        }
         this.applyFilters();
     }
 
@@ -1251,12 +2312,95 @@ const JS_APP: &str = r#"class JsonWebLogApp {
         }
     }
 
-    showColumnConfig() {
+    async showColumnConfig() {
         this.isConfigPanelOpen = true;
         this.elements.columnConfigPanel.style.display = 'flex';
+        await this.loadFieldCatalog();
         this.renderColumnVisibilityList();
     }
 
+    async loadFieldCatalog() {
+        // Fields discovered after the table was configured become available as hidden columns
+        try {
+            const response = await fetch('/api/schema');
+            if (!response.ok) return;
+            const schema = await response.json();
+            this.fieldCatalog = schema.catalog || {};
+
+            const known = new Set(this.columns.map(column => column.field_name));
+            let nextOrder = this.columns.reduce((max, column) => Math.max(max, column.order || 0), 0) + 1;
+            const candidates = (schema.fields || []).concat(Object.keys(this.fieldCatalog));
+            candidates.forEach(fieldName => {
+                const info = this.fieldCatalog[fieldName];
+                // Objects are offered through their nested paths instead
+                if (known.has(fieldName) || (info && info.types.length === 1 && info.types[0] === 'object')) {
+                    return;
+                }
+                known.add(fieldName);
+                this.columns.push({ field_name: fieldName, width: 150, visible: false, order: nextOrder++ });
+            });
+        } catch (error) {
+            console.error('Failed to load field catalog:', error);
+        }
+    }
+
+    handleSchemaChanges(changes) {
+        changes.forEach(change => {
+            // Fields that already have a column, visible or hidden by the user, need no prompt
+            if (change.kind === 'field_added' && this.columns.some(column => column.field_name === change.path)) {
+                return;
+            }
+            this.schemaChanges.set(change.path, change);
+        });
+        this.renderSchemaNotice();
+    }
+
+    renderSchemaNotice() {
+        if (this.schemaChanges.size === 0) {
+            this.elements.schemaNotice.style.display = 'none';
+            this.elements.schemaNotice.innerHTML = '';
+            return;
+        }
+
+        const items = [...this.schemaChanges.values()].map(change => {
+            const path = this.escapeHtml(change.path);
+            const pathArg = this.escapeHtml(JSON.stringify(change.path));
+            if (change.kind === 'field_added') {
+                return `<span class="schema-notice-item">새 필드 <code>${path}</code> (${change.field_type})
+                    <button class="btn btn-primary" onclick="app.addColumnFromNotice(${pathArg})">컬럼 추가</button></span>`;
+            }
+            return `<span class="schema-notice-item">타입 변경 <code>${path}</code> ${change.previous.join('|')} → ${change.field_type}</span>`;
+        }).join('');
+
+        this.elements.schemaNotice.innerHTML = `${items}
+            <button class="btn btn-secondary" onclick="app.dismissSchemaNotice()">닫기</button>`;
+        this.elements.schemaNotice.style.display = 'flex';
+    }
+
+    dismissSchemaNotice() {
+        this.schemaChanges.clear();
+        this.renderSchemaNotice();
+    }
+
+    async addColumnFromNotice(fieldName) {
+        this.schemaChanges.delete(fieldName);
+        this.renderSchemaNotice();
+        if (this.columns.some(column => column.field_name === fieldName)) {
+            return;
+        }
+        const nextOrder = this.columns.reduce((max, column) => Math.max(max, column.order || 0), 0) + 1;
+        this.columns.push({ field_name: fieldName, width: 150, visible: true, order: nextOrder });
+        await this.applyColumnConfiguration();
+    }
+
+    describeField(fieldName) {
+        const info = this.fieldCatalog[fieldName];
+        if (!info) return '';
+        const examples = info.examples.map(value => JSON.stringify(value)).join(', ');
+        const cardinality = `${info.cardinality}${info.cardinality_capped ? '+' : ''}`;
+        return `${info.types.join('|')} · ${info.coverage}% · 고유값 ${cardinality}${examples ? ' · 예: ' + examples : ''}`;
+    }
+
     hideColumnConfig() {
         this.isConfigPanelOpen = false;
         this.elements.columnConfigPanel.style.display = 'none';
@@ -1278,7 +2422,7 @@ const JS_APP: &str = r#"class JsonWebLogApp {
                     <input type="checkbox" id="col-${originalIndex}" ${column.visible ? 'checked' : ''} 
                            onchange="app.toggleColumnVisibility(${originalIndex}, this.checked)">
                     <div class="column-info">
-                        <span class="column-name">${column.field_name}</span>
+                        <span class="column-name" title="${this.escapeHtml(this.describeField(column.field_name))}">${column.field_name}</span>
                         <span class="column-width">${column.width}px</span>
                     </div>
                 </div>
@@ -0,0 +1,552 @@
+//! Server side of the WebSocket protocol (RFC 6455) with the permessage-deflate
+//! extension (RFC 7692).
+//!
+//! axum's WebSocket support cannot negotiate extensions, so the handshake and the
+//! connection are handled here on top of the upgraded HTTP connection, using
+//! tungstenite's frame header codec. When the client offers permessage-deflate, which
+//! browsers do by default, every data message is compressed with raw deflate and the
+//! compression context is kept across messages unless the client asks otherwise.
+
+use axum::{
+    async_trait,
+    body::Body,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, HeaderValue, Method, StatusCode},
+    response::Response,
+};
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+use hyper::upgrade::{OnUpgrade, Upgraded};
+use hyper_util::rt::TokioIo;
+use std::future::Future;
+use std::io::{self, Cursor};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tungstenite::protocol::frame::{
+    coding::{Control, Data, OpCode},
+    FrameHeader,
+};
+
+/// Largest message accepted from a client, after decompression. Clients only send
+/// small subscribe requests.
+pub const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+/// Trailer of a sync flush, which permessage-deflate leaves off the wire.
+const SYNC_FLUSH_TRAILER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close,
+}
+
+/// Accepted permessage-deflate parameters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeflateConfig {
+    /// The server resets its compressor after every message
+    pub server_no_context_takeover: bool,
+    /// The client resets its compressor after every message
+    pub client_no_context_takeover: bool,
+}
+
+impl DeflateConfig {
+    /// Picks the first permessage-deflate offer in `Sec-WebSocket-Extensions` that can be
+    /// honored. Offers limiting the server's window below 32 KiB are declined, as the
+    /// deflate backend always uses the full window.
+    pub fn negotiate(offers: &str) -> Option<Self> {
+        offers.split(',').find_map(|offer| {
+            let mut params = offer.split(';').map(str::trim);
+            if !params.next()?.eq_ignore_ascii_case("permessage-deflate") {
+                return None;
+            }
+            let mut config = DeflateConfig::default();
+            let mut seen = Vec::new();
+            for param in params {
+                let (name, value) = match param.split_once('=') {
+                    Some((name, value)) => (name.trim(), Some(value.trim().trim_matches('"'))),
+                    None => (param, None),
+                };
+                if seen.contains(&name) {
+                    return None;
+                }
+                seen.push(name);
+                match (name, value) {
+                    ("server_no_context_takeover", None) => config.server_no_context_takeover = true,
+                    ("client_no_context_takeover", None) => config.client_no_context_takeover = true,
+                    ("server_max_window_bits", Some("15")) => {}
+                    // Inflating accepts any window the client compresses with
+                    ("client_max_window_bits", None) => {}
+                    ("client_max_window_bits", Some(bits)) if bits.parse::<u8>().is_ok_and(|bits| (8..=15).contains(&bits)) => {}
+                    _ => return None,
+                }
+            }
+            Some(config)
+        })
+    }
+
+    /// `Sec-WebSocket-Extensions` value confirming this configuration.
+    pub fn response_header(&self) -> String {
+        let mut header = String::from("permessage-deflate");
+        if self.server_no_context_takeover {
+            header.push_str("; server_no_context_takeover");
+        }
+        if self.client_no_context_takeover {
+            header.push_str("; client_no_context_takeover");
+        }
+        header
+    }
+}
+
+/// Extractor for a WebSocket handshake request.
+pub struct WebSocketUpgrade {
+    key: HeaderValue,
+    on_upgrade: OnUpgrade,
+    deflate: Option<DeflateConfig>,
+}
+
+impl WebSocketUpgrade {
+    /// Answers the handshake and runs `callback` with the socket once the connection
+    /// has been upgraded.
+    pub fn on_upgrade<F, Fut>(self, callback: F) -> Response
+    where
+        F: FnOnce(WebSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let deflate = self.deflate;
+        let on_upgrade = self.on_upgrade;
+        tokio::spawn(async move {
+            // Fails when the client goes away before the upgrade completes
+            if let Ok(upgraded) = on_upgrade.await {
+                callback(WebSocket::new(TokioIo::new(upgraded), deflate)).await;
+            }
+        });
+
+        let mut response = Response::builder()
+            .status(StatusCode::SWITCHING_PROTOCOLS)
+            .header(header::CONNECTION, "upgrade")
+            .header(header::UPGRADE, "websocket")
+            .header(header::SEC_WEBSOCKET_ACCEPT, tungstenite::handshake::derive_accept_key(self.key.as_bytes()));
+        if let Some(deflate) = deflate {
+            response = response.header(header::SEC_WEBSOCKET_EXTENSIONS, deflate.response_header());
+        }
+        response.body(Body::empty()).unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for WebSocketUpgrade {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let reject = |message: &str| (StatusCode::BAD_REQUEST, message.to_string());
+        if parts.method != Method::GET {
+            return Err((StatusCode::METHOD_NOT_ALLOWED, "WebSocket requests must use GET".to_string()));
+        }
+        if !header_has_token(&parts.headers, header::CONNECTION, "upgrade") {
+            return Err(reject("Connection header did not include 'upgrade'"));
+        }
+        if !header_has_token(&parts.headers, header::UPGRADE, "websocket") {
+            return Err(reject("Upgrade header did not include 'websocket'"));
+        }
+        if parts.headers.get(header::SEC_WEBSOCKET_VERSION).map(HeaderValue::as_bytes) != Some(b"13") {
+            return Err(reject("Sec-WebSocket-Version must be 13"));
+        }
+        let key = parts
+            .headers
+            .get(header::SEC_WEBSOCKET_KEY)
+            .cloned()
+            .ok_or_else(|| reject("Missing Sec-WebSocket-Key header"))?;
+        let on_upgrade = parts
+            .extensions
+            .remove::<OnUpgrade>()
+            .ok_or_else(|| (StatusCode::UPGRADE_REQUIRED, "Connection cannot be upgraded".to_string()))?;
+
+        let offers: Vec<&str> = parts
+            .headers
+            .get_all(header::SEC_WEBSOCKET_EXTENSIONS)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect();
+        Ok(Self {
+            key,
+            on_upgrade,
+            deflate: DeflateConfig::negotiate(&offers.join(",")),
+        })
+    }
+}
+
+/// Whether a comma-separated header lists `token`, ignoring case.
+fn header_has_token(headers: &HeaderMap, name: header::HeaderName, token: &str) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|item| item.trim().eq_ignore_ascii_case(token))
+}
+
+/// An established WebSocket connection.
+pub struct WebSocket<S = TokioIo<Upgraded>> {
+    stream: S,
+    deflate: Option<DeflateConfig>,
+}
+
+impl<S: AsyncRead + AsyncWrite> WebSocket<S> {
+    pub fn new(stream: S, deflate: Option<DeflateConfig>) -> Self {
+        Self { stream, deflate }
+    }
+
+    /// Splits the connection so messages can be sent from another task.
+    pub fn split(self) -> (Sender<WriteHalf<S>>, Receiver<ReadHalf<S>>) {
+        let (reader, writer) = tokio::io::split(self.stream);
+        let sender = Sender {
+            writer,
+            deflater: self
+                .deflate
+                .map(|config| Deflater::new(config.server_no_context_takeover)),
+            buffer: Vec::new(),
+        };
+        let receiver = Receiver {
+            reader,
+            inflater: self
+                .deflate
+                .map(|config| Inflater::new(config.client_no_context_takeover)),
+            buffer: Vec::new(),
+        };
+        (sender, receiver)
+    }
+}
+
+/// Sending half of a connection.
+pub struct Sender<W> {
+    writer: W,
+    deflater: Option<Deflater>,
+    buffer: Vec<u8>,
+}
+
+impl<W: AsyncWrite + Unpin> Sender<W> {
+    pub async fn send(&mut self, message: Message) -> io::Result<()> {
+        let (opcode, payload) = match message {
+            Message::Text(text) => (OpCode::Data(Data::Text), text.into_bytes()),
+            Message::Binary(bytes) => (OpCode::Data(Data::Binary), bytes),
+            Message::Ping(payload) => (OpCode::Control(Control::Ping), payload),
+            Message::Pong(payload) => (OpCode::Control(Control::Pong), payload),
+            Message::Close => (OpCode::Control(Control::Close), Vec::new()),
+        };
+        // Control frames are never compressed
+        let (payload, compressed) = match (&mut self.deflater, opcode) {
+            (Some(deflater), OpCode::Data(_)) => (deflater.compress(&payload)?, true),
+            _ => (payload, false),
+        };
+
+        let header = FrameHeader {
+            is_final: true,
+            rsv1: compressed,
+            opcode,
+            ..FrameHeader::default()
+        };
+        self.buffer.clear();
+        header
+            .format(payload.len() as u64, &mut self.buffer)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.buffer.extend_from_slice(&payload);
+        self.writer.write_all(&self.buffer).await?;
+        self.writer.flush().await
+    }
+
+    /// Sends a close frame and shuts the connection down.
+    pub async fn close(&mut self) -> io::Result<()> {
+        self.send(Message::Close).await?;
+        self.writer.shutdown().await
+    }
+}
+
+/// Receiving half of a connection.
+pub struct Receiver<R> {
+    reader: R,
+    inflater: Option<Inflater>,
+    buffer: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> Receiver<R> {
+    /// Next complete message; `None` once the connection is closed. Pings are returned
+    /// for the caller to answer, as only the sending half can write.
+    pub async fn next(&mut self) -> Option<io::Result<Message>> {
+        self.read_message().await.transpose()
+    }
+
+    async fn read_message(&mut self) -> io::Result<Option<Message>> {
+        // Kind, compression and payload of a fragmented message being reassembled
+        let mut partial: Option<(Data, bool, Vec<u8>)> = None;
+        loop {
+            let Some((header, mut payload)) = self.read_frame().await? else {
+                return Ok(None);
+            };
+            let Some(mask) = header.mask else {
+                return Err(protocol_error("Client frames must be masked"));
+            };
+            for (index, byte) in payload.iter_mut().enumerate() {
+                *byte ^= mask[index % 4];
+            }
+            if header.rsv2 || header.rsv3 {
+                return Err(protocol_error("Reserved bits are set"));
+            }
+
+            let data = match header.opcode {
+                OpCode::Control(control) => {
+                    if header.rsv1 || !header.is_final || payload.len() > 125 {
+                        return Err(protocol_error("Invalid control frame"));
+                    }
+                    match control {
+                        Control::Ping => return Ok(Some(Message::Ping(payload))),
+                        Control::Pong => continue,
+                        Control::Close => return Ok(Some(Message::Close)),
+                        Control::Reserved(_) => return Err(protocol_error("Unknown opcode")),
+                    }
+                }
+                OpCode::Data(data) => data,
+            };
+            match (data, &mut partial) {
+                (Data::Text | Data::Binary, None) => {
+                    if header.rsv1 && self.inflater.is_none() {
+                        return Err(protocol_error("Compressed frame without permessage-deflate"));
+                    }
+                    partial = Some((data, header.rsv1, payload));
+                }
+                (Data::Continue, Some((_, _, buffer))) if !header.rsv1 => {
+                    buffer.extend_from_slice(&payload);
+                    if buffer.len() > MAX_MESSAGE_SIZE {
+                        return Err(protocol_error("Message too large"));
+                    }
+                }
+                _ => return Err(protocol_error("Unexpected data frame")),
+            }
+            if !header.is_final {
+                continue;
+            }
+
+            let Some((kind, compressed, payload)) = partial.take() else {
+                continue;
+            };
+            let payload = match &mut self.inflater {
+                Some(inflater) if compressed => inflater.decompress(&payload)?,
+                _ => payload,
+            };
+            return match kind {
+                Data::Text => String::from_utf8(payload)
+                    .map(|text| Some(Message::Text(text)))
+                    .map_err(|_| protocol_error("Text message is not UTF-8")),
+                _ => Ok(Some(Message::Binary(payload))),
+            };
+        }
+    }
+
+    /// Reads one frame's header and (still masked) payload.
+    async fn read_frame(&mut self) -> io::Result<Option<(FrameHeader, Vec<u8>)>> {
+        loop {
+            let mut cursor = Cursor::new(&self.buffer);
+            if let Some((header, len)) = FrameHeader::parse(&mut cursor).map_err(|e| protocol_error(&e.to_string()))? {
+                if len > MAX_MESSAGE_SIZE as u64 {
+                    return Err(protocol_error("Message too large"));
+                }
+                let start = cursor.position() as usize;
+                let end = start + len as usize;
+                if self.buffer.len() >= end {
+                    let payload = self.buffer[start..end].to_vec();
+                    self.buffer.drain(..end);
+                    return Ok(Some((header, payload)));
+                }
+            }
+
+            if self.reader.read_buf(&mut self.buffer).await? == 0 {
+                return if self.buffer.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(io::ErrorKind::UnexpectedEof.into())
+                };
+            }
+        }
+    }
+}
+
+fn protocol_error(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Compresses outgoing messages.
+struct Deflater {
+    compress: Compress,
+    reset_after_message: bool,
+}
+
+impl Deflater {
+    fn new(reset_after_message: bool) -> Self {
+        Self {
+            compress: Compress::new(Compression::fast(), false),
+            reset_after_message,
+        }
+    }
+
+    fn compress(&mut self, input: &[u8]) -> io::Result<Vec<u8>> {
+        let mut output = Vec::with_capacity(input.len() / 2 + 64);
+        let start = self.compress.total_in();
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+            self.compress
+                .compress_vec(&input[consumed..], &mut output, FlushCompress::Sync)
+                .map_err(io::Error::other)?;
+            // The flush is complete once all input is taken and output space is left over
+            let consumed = (self.compress.total_in() - start) as usize;
+            if consumed == input.len() && output.len() < output.capacity() {
+                break;
+            }
+            output.reserve(output.capacity().max(64));
+        }
+        if output.ends_with(&SYNC_FLUSH_TRAILER) {
+            output.truncate(output.len() - SYNC_FLUSH_TRAILER.len());
+        }
+        if self.reset_after_message {
+            self.compress.reset();
+        }
+        Ok(output)
+    }
+}
+
+/// Decompresses incoming messages.
+struct Inflater {
+    decompress: Decompress,
+    reset_after_message: bool,
+}
+
+impl Inflater {
+    fn new(reset_after_message: bool) -> Self {
+        Self {
+            decompress: Decompress::new(false),
+            reset_after_message,
+        }
+    }
+
+    fn decompress(&mut self, payload: &[u8]) -> io::Result<Vec<u8>> {
+        let mut input = Vec::with_capacity(payload.len() + SYNC_FLUSH_TRAILER.len());
+        input.extend_from_slice(payload);
+        input.extend_from_slice(&SYNC_FLUSH_TRAILER);
+
+        let mut output = Vec::with_capacity(payload.len() * 2 + 64);
+        let start = self.decompress.total_in();
+        loop {
+            let consumed = (self.decompress.total_in() - start) as usize;
+            let status = self
+                .decompress
+                .decompress_vec(&input[consumed..], &mut output, FlushDecompress::Sync)
+                .map_err(|e| protocol_error(&e.to_string()))?;
+            let consumed = (self.decompress.total_in() - start) as usize;
+            // A final block ends the stream; the next message starts a new one
+            if status == Status::StreamEnd {
+                self.decompress.reset(false);
+                break;
+            }
+            if consumed == input.len() && output.len() < output.capacity() {
+                break;
+            }
+            if output.len() >= MAX_MESSAGE_SIZE {
+                return Err(protocol_error("Message too large"));
+            }
+            output.reserve(output.capacity().max(64));
+        }
+        if self.reset_after_message {
+            self.decompress.reset(false);
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiates_permessage_deflate() {
+        assert_eq!(DeflateConfig::negotiate(""), None);
+        assert_eq!(DeflateConfig::negotiate("x-webkit-deflate-frame"), None);
+        assert_eq!(
+            DeflateConfig::negotiate("permessage-deflate; client_max_window_bits"),
+            Some(DeflateConfig::default())
+        );
+        // A smaller server window is declined in favor of the next offer
+        let config = DeflateConfig::negotiate(
+            "permessage-deflate; server_max_window_bits=10, permessage-deflate; server_no_context_takeover",
+        )
+        .unwrap();
+        assert!(config.server_no_context_takeover);
+        assert_eq!(config.response_header(), "permessage-deflate; server_no_context_takeover");
+        assert_eq!(DeflateConfig::negotiate("permessage-deflate; unknown"), None);
+        assert_eq!(
+            DeflateConfig::negotiate("permessage-deflate; client_no_context_takeover; client_no_context_takeover"),
+            None
+        );
+    }
+
+    /// Masks a client frame the way a browser would send it.
+    fn client_frame(opcode: OpCode, rsv1: bool, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let header = FrameHeader {
+            is_final: true,
+            rsv1,
+            opcode,
+            mask: Some(mask),
+            ..FrameHeader::default()
+        };
+        let mut frame = Vec::new();
+        header.format(payload.len() as u64, &mut frame).unwrap();
+        frame.extend(payload.iter().enumerate().map(|(index, byte)| byte ^ mask[index % 4]));
+        frame
+    }
+
+    #[tokio::test]
+    async fn test_exchanges_compressed_messages() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (mut sender, mut receiver) = WebSocket::new(server, Some(DeflateConfig::default())).split();
+        let (mut client_reader, mut client_writer) = tokio::io::split(client);
+
+        // The client keeps its compression context across messages, as browsers do
+        let mut client_deflater = Deflater::new(false);
+        for text in [r#"{"type":"subscribe"}"#, r#"{"type":"subscribe"}"#] {
+            let payload = client_deflater.compress(text.as_bytes()).unwrap();
+            client_writer.write_all(&client_frame(OpCode::Data(Data::Text), true, &payload)).await.unwrap();
+            assert_eq!(receiver.next().await.unwrap().unwrap(), Message::Text(text.to_string()));
+        }
+        client_writer.write_all(&client_frame(OpCode::Control(Control::Ping), false, b"hi")).await.unwrap();
+        assert_eq!(receiver.next().await.unwrap().unwrap(), Message::Ping(b"hi".to_vec()));
+
+        let entry = r#"{"seq":1,"message":"connection reset by peer"}"#.repeat(20);
+        sender.send(Message::Text(entry.clone())).await.unwrap();
+        sender.send(Message::Text(entry.clone())).await.unwrap();
+        sender.close().await.unwrap();
+
+        let mut received = Vec::new();
+        client_reader.read_to_end(&mut received).await.unwrap();
+        let mut cursor = Cursor::new(&received);
+        let mut client_inflater = Inflater::new(false);
+        for _ in 0..2 {
+            let (header, len) = FrameHeader::parse(&mut cursor).unwrap().unwrap();
+            assert!(header.rsv1 && header.mask.is_none());
+            assert!((len as usize) < entry.len() / 4);
+            let start = cursor.position() as usize;
+            let payload = &received[start..start + len as usize];
+            cursor.set_position((start + len as usize) as u64);
+            assert_eq!(client_inflater.decompress(payload).unwrap(), entry.as_bytes());
+        }
+        let (header, _) = FrameHeader::parse(&mut cursor).unwrap().unwrap();
+        assert_eq!(header.opcode, OpCode::Control(Control::Close));
+        assert!(!header.rsv1);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_compressed_frames_unless_negotiated() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let (_, mut receiver) = WebSocket::new(server, None).split();
+        client.write_all(&client_frame(OpCode::Data(Data::Text), true, b"abc")).await.unwrap();
+        assert!(receiver.next().await.unwrap().is_err());
+    }
+}
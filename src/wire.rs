@@ -0,0 +1,159 @@
+//! Encoding of WebSocket frames.
+//!
+//! By default every log entry is sent as its own frame, while control messages are
+//! objects with a `type` field. Clients that opt into batching receive entries as
+//! arrays instead. Frames are JSON text unless the client asks for MessagePack when it
+//! connects, in which case they are binary.
+//!
+//! Compression is left to the permessage-deflate extension, negotiated during the
+//! handshake in `websocket`, so clients read frames the same way either way.
+
+use serde::{Deserialize, Serialize};
+
+/// Entries per batch before it is sent regardless of size.
+pub const MAX_BATCH_ENTRIES: usize = 1000;
+/// Encoded bytes per batch before it is sent.
+pub const MAX_BATCH_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    #[default]
+    Json,
+    #[serde(rename = "msgpack")]
+    MessagePack,
+}
+
+#[derive(Debug)]
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameEncoder {
+    encoding: Encoding,
+}
+
+impl FrameEncoder {
+    pub fn new(encoding: Encoding) -> Self {
+        Self { encoding }
+    }
+
+    /// Encodes one value in the negotiated encoding.
+    pub fn encode<T: Serialize>(&self, value: &T) -> anyhow::Result<Vec<u8>> {
+        Ok(match self.encoding {
+            Encoding::Json => serde_json::to_vec(value)?,
+            // Named fields keep entries self-describing, like the JSON objects
+            Encoding::MessagePack => rmp_serde::to_vec_named(value)?,
+        })
+    }
+
+    /// Wraps an encoded value into a frame.
+    pub fn frame(&self, payload: Vec<u8>) -> anyhow::Result<Frame> {
+        match self.encoding {
+            Encoding::Json => Ok(Frame::Text(String::from_utf8(payload)?)),
+            Encoding::MessagePack => Ok(Frame::Binary(payload)),
+        }
+    }
+
+    /// Joins values produced by `encode` into an array.
+    pub fn array(&self, items: &[Vec<u8>]) -> Vec<u8> {
+        let len: usize = items.iter().map(Vec::len).sum();
+        let mut payload = Vec::with_capacity(len + items.len() + 5);
+        match self.encoding {
+            Encoding::Json => {
+                payload.push(b'[');
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        payload.push(b',');
+                    }
+                    payload.extend_from_slice(item);
+                }
+                payload.push(b']');
+            }
+            Encoding::MessagePack => {
+                // fixarray, array 16 or array 32 header
+                if items.len() < 16 {
+                    payload.push(0x90 | items.len() as u8);
+                } else if let Ok(len) = u16::try_from(items.len()) {
+                    payload.push(0xdc);
+                    payload.extend_from_slice(&len.to_be_bytes());
+                } else {
+                    payload.push(0xdd);
+                    payload.extend_from_slice(&(items.len() as u32).to_be_bytes());
+                }
+                for item in items {
+                    payload.extend_from_slice(item);
+                }
+            }
+        }
+        payload
+    }
+}
+
+/// Encoded entries waiting to be sent as one frame.
+#[derive(Debug, Default)]
+pub struct Batch {
+    items: Vec<Vec<u8>>,
+    bytes: usize,
+}
+
+impl Batch {
+    pub fn push(&mut self, item: Vec<u8>) {
+        self.bytes += item.len();
+        self.items.push(item);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.items.len() >= MAX_BATCH_ENTRIES || self.bytes >= MAX_BATCH_BYTES
+    }
+
+    /// Takes the batch as a single frame, leaving it empty.
+    pub fn take_frame(&mut self, encoder: &FrameEncoder) -> anyhow::Result<Frame> {
+        self.bytes = 0;
+        let items = std::mem::take(&mut self.items);
+        encoder.frame(encoder.array(&items))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn batch_of(encoder: &FrameEncoder, count: usize) -> Batch {
+        let mut batch = Batch::default();
+        for seq in 0..count {
+            batch.push(encoder.encode(&json!({"seq": seq, "message": "hello"})).unwrap());
+        }
+        batch
+    }
+
+    #[test]
+    fn test_batches_in_each_encoding() {
+        let json = FrameEncoder::default();
+        let Frame::Text(text) = batch_of(&json, 2).take_frame(&json).unwrap() else {
+            panic!("JSON frames are text");
+        };
+        let entries: Vec<Value> = serde_json::from_str(&text).unwrap();
+        assert_eq!(entries[1]["seq"], 1);
+
+        for count in [3, 20, 70_000] {
+            let msgpack = FrameEncoder::new(Encoding::MessagePack);
+            let mut batch = batch_of(&msgpack, count);
+            assert_eq!(batch.is_full(), count >= MAX_BATCH_ENTRIES);
+            let Frame::Binary(bytes) = batch.take_frame(&msgpack).unwrap() else {
+                panic!("MessagePack frames are binary");
+            };
+            assert!(batch.is_empty());
+            let entries: Vec<Value> = rmp_serde::from_slice(&bytes).unwrap();
+            assert_eq!(entries.len(), count);
+            assert_eq!(entries[count - 1]["message"], "hello");
+        }
+    }
+}